@@ -46,22 +46,32 @@ impl Artwork for Model {
     fn get_mut_model(&mut self) -> &mut BaseModel {
         &mut self.base
     }
+    fn new(base: BaseModel) -> Model {
+        let mut model = Model {
+            base,
+            palettes: vec![],
+        };
+        model.reseed();
+        model
+    }
+    fn reseed(&mut self) {
+        let mut rng = self.base.rng();
+        self.palettes = (0..=N)
+            .map(|_| get_random_color_palette(P + 1, PosFunctions::LinearPosition, &mut rng))
+            .collect();
+    }
     fn key_pressed(&mut self, _app: &App, key: Key) {
         match key {
-            Key::S => {
+            // The palettes of the seed, through three colours instead of two.
+            Key::T => {
+                let mut rng = self.base.rng();
                 self.palettes = (0..=N)
-                    .map(|_| get_random_color_palette3(P + 1, PosFunctions::LinearPosition))
+                    .map(|_| {
+                        get_random_color_palette3(P + 1, PosFunctions::LinearPosition, &mut rng)
+                    })
                     .collect();
             }
             _ => {}
         }
     }
-    fn new(base: BaseModel) -> Model {
-        Model {
-            base,
-            palettes: (0..=N)
-                .map(|_| get_random_color_palette(P + 1, PosFunctions::LinearPosition))
-                .collect(),
-        }
-    }
 }
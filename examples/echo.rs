@@ -0,0 +1,81 @@
+use artworks::{feedback::Trail, make_recorder_app, Artwork, BaseModel};
+use nannou::{prelude::*, rand::Rng};
+
+fn main() {
+    make_recorder_app::<Model>().run();
+}
+
+struct Model {
+    pub base: BaseModel,
+    dots: Vec<Dot>,
+}
+
+/// A dot going around a circle a whole number of times per loop.
+struct Dot {
+    radius: f32,
+    turns: f32,
+    phase: f32,
+    size: f32,
+}
+
+const N_DOTS: usize = 12;
+
+impl Artwork for Model {
+    fn draw_at_time(&mut self, time: f64) {
+        // First, reset the `draw` state.
+        let draw = &self.base.draw;
+        draw.reset();
+        let [w, _h] = self.base.texture.size();
+        draw.background()
+            .color(srgba(0.08627, 0.08627, 0.08627, 1.));
+
+        // The previous frame, slightly zoomed and turned, leaves the echoes of
+        // the dots spiralling outwards.
+        if let Some(feedback) = &self.base.feedback {
+            Trail::new()
+                .scale(1.015)
+                .rotate(0.01)
+                .fade(0.06)
+                .fade_color(srgb(0.08627, 0.08627, 0.08627))
+                .draw(draw, feedback);
+        }
+        for dot in &self.dots {
+            let angle = TAU * (dot.turns * time as f32 + dot.phase);
+            let radius = dot.radius * w as f32;
+            draw.ellipse()
+                .color(srgba(0.95, 0.92, 0.85, 1.))
+                .radius(dot.size)
+                .x_y(radius * angle.cos(), radius * angle.sin());
+        }
+    }
+
+    fn get_model(&self) -> &BaseModel {
+        &self.base
+    }
+    fn get_mut_model(&mut self) -> &mut BaseModel {
+        &mut self.base
+    }
+    fn new(base: BaseModel) -> Model {
+        let dots = dots(&mut base.rng());
+        Model { base, dots }
+    }
+
+    fn reseed(&mut self) {
+        self.dots = dots(&mut self.base.rng());
+    }
+
+    fn feedback_preroll() -> Option<u32> {
+        Some(120)
+    }
+}
+
+fn dots(rng: &mut impl Rng) -> Vec<Dot> {
+    (0..N_DOTS)
+        .map(|_| Dot {
+            radius: rng.gen_range(0.05..0.3),
+            turns: rng.gen_range(1..4) as f32 * if rng.gen() { 1. } else { -1. },
+            phase: rng.gen(),
+            size: rng.gen_range(6.0..20.0),
+        })
+        .collect()
+}
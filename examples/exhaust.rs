@@ -1,5 +1,4 @@
-use artworks::{make_recorder_app, Artwork, BaseModel, Options};
-use nannou::noise::{NoiseFn, OpenSimplex};
+use artworks::{make_recorder_app, utils::noise::LoopNoise, Artwork, BaseModel, Options};
 use nannou::{draw::mesh::vertex::Point, prelude::*};
 
 fn main() {
@@ -8,7 +7,10 @@ fn main() {
 
 struct Model {
     pub base: BaseModel,
-    noise: OpenSimplex,
+    /// Offsets the angle of the pieces.
+    noise: LoopNoise,
+    /// Offsets the radius of the rings.
+    radius_noise: LoopNoise,
 }
 
 const OFFSET: f32 = PI / 15.;
@@ -23,36 +25,31 @@ const N_RINGS: usize = 6;
 fn get_points(
     radial_number: f32,
     ring_number: isize,
-    time: f32,
-    noise: &OpenSimplex,
-    seed: f64,
+    time: f64,
+    (noise, radius_noise): (&LoopNoise, &LoopNoise),
 ) -> (Point2, Point2) {
     if ring_number == -1 {
         return (Point2::new(0., 0.), Point2::new(0., 0.));
     }
     let nc = radial_number;
+    let loop_time = time;
+    let time = time as f32 * TAU;
     let inc =
         nc + (if (ring_number) % 2 == 0 { -1. } else { 1. }) * OFFSET / (ring_number as f32 + 1.);
 
     let noisy_time_angle_offset = TAU
         * noise
-            .get([
-                1. * ring_number as f64 / N_RINGS as f64,
-                2. * (time.cos() as f64 + time.sin() as f64),
-                seed / 1.,
+            .get(
+                ring_number as f64 / N_RINGS as f64,
                 radial_number as f64 / SEP as f64,
-            ])
+                loop_time,
+            )
             .sin() as f32;
     let s = 1.
         + (if (ring_number) % 2 == 0 { -1. } else { 1. }) * (time + noisy_time_angle_offset).sin()
             / 2.;
-    let noisy_radius = 0.4
-        * noise.get([
-            1. * ring_number as f64 / N_RINGS as f64,
-            1. * (time.cos() as f64 + time.sin() as f64) / 10.,
-            seed + 4.,
-            // radial_number as f64 / SEP as f64,
-        ]) as f32;
+    let noisy_radius =
+        0.4 * radius_noise.get(ring_number as f64 / N_RINGS as f64, 0., loop_time) as f32;
     let rd = ((ring_number + 1) as f32 + noisy_radius) * K
         + FAC * (nc + (ring_number + 1) as f32 * PI / 2. + time).cos();
     let theta_left = inc + (if (ring_number) % 2 == 0 { -1. } else { 1. }) * 0.5 * STEP * s;
@@ -84,8 +81,6 @@ impl Artwork for Model {
 
         // Get the width and height of the animation.
         let [_w, _h] = self.base.texture.size();
-        // Set the seed
-        let seed = (self.base.seed % 1000) as f64 / 1000.;
 
         draw.background()
             .color(srgba(0.08627, 0.08627, 0.08627, 1.));
@@ -96,8 +91,9 @@ impl Artwork for Model {
             let nc = TAU * (val as f32 / SEP as f32) + time / 2.;
 
             (0..N_RINGS).for_each(|rg| {
-                let (p0, p1) = get_points(nc, rg as isize - 1, time, &self.noise, seed);
-                let (p2, p3) = get_points(nc, rg as isize, time, &self.noise, seed);
+                let noises = (&self.noise, &self.radius_noise);
+                let (p0, p1) = get_points(nc, rg as isize - 1, btime, noises);
+                let (p2, p3) = get_points(nc, rg as isize, btime, noises);
                 draw_pieces(
                     draw,
                     p0,
@@ -117,9 +113,19 @@ impl Artwork for Model {
         &mut self.base
     }
     fn new(base: BaseModel) -> Model {
-        let noise = OpenSimplex::new();
+        // The angles wander a lot over a loop, the radii barely.
+        let noise = LoopNoise::new(base.seed).radius(1.4);
+        let radius_noise = LoopNoise::new(base.seed).radius(0.14).center(4., 0.);
+        Model {
+            base,
+            noise,
+            radius_noise,
+        }
+    }
 
-        Model { base, noise }
+    fn reseed(&mut self) {
+        self.noise.reseed(self.base.seed);
+        self.radius_noise.reseed(self.base.seed);
     }
 
     fn n_sec(&self) -> Option<u32> {
@@ -132,6 +138,7 @@ impl Artwork for Model {
             sample_per_frame: 10,
             shutter_angle: 0.5,
             extra_tex: None,
+            footage: None,
             noise_amount: 0.1,
         })
     }
@@ -1,6 +1,9 @@
-use artworks::{make_recorder_app, Artwork, BaseModel, Options};
+use artworks::{make_recorder_app, utils::noise::LoopNoise, Artwork, BaseModel, Options};
 use colourado::{Color, ColorPalette, PaletteType};
-use nannou::{noise, noise::NoiseFn, prelude::*};
+use nannou::{
+    prelude::*,
+    rand::{rngs::StdRng, Rng},
+};
 
 fn main() {
     make_recorder_app::<Model>().run();
@@ -35,9 +38,13 @@ fn draw_path(draw: &Draw, l_path: &LinePath) {
 struct GridFlow {
     init_points: Vec<LinePath>,
     points: Vec<LinePath>,
-    noise: noise::OpenSimplex,
+    noise: LoopNoise,
+    /// The noise of the y coordinate, further along the time dimensions.
+    noise_y: LoopNoise,
     width: f32,
     palette: ColorPalette,
+    /// Places the lines, seeded with the seed of the artwork.
+    rng: StdRng,
 }
 
 const N_LINES: u32 = 50;
@@ -45,7 +52,7 @@ const L_MIN: usize = 50;
 const L_MAX: usize = 300;
 
 impl GridFlow {
-    fn update(&mut self, fact: f32, tau: f64, time: f64) {
+    fn update(&mut self, fact: f32, time: f64) {
         let w = self.width;
         for (n, p) in self.points.iter_mut().enumerate() {
             let mut use_init = false;
@@ -67,37 +74,36 @@ impl GridFlow {
                     p.points.push(Point2::new(
                         last_p.x
                             - fact
-                                * self.noise.get([
+                                * self.noise.get(
                                     last_p.x as f64 / (5. * N_LINES as f64),
                                     last_p.y as f64 / (5. * N_LINES as f64),
-                                    tau.cos() / 5.,
-                                    tau.sin() / 5.,
-                                ]) as f32,
+                                    time,
+                                ) as f32,
                         last_p.y
                             - fact
-                                * self.noise.get([
+                                * self.noise_y.get(
                                     last_p.x as f64 / (5. * N_LINES as f64),
                                     last_p.y as f64 / (5. * N_LINES as f64),
-                                    tau.cos() / 5. + 2.,
-                                    tau.sin() / 5. - 1.,
-                                ]) as f32,
+                                    time,
+                                ) as f32,
                     ));
                 } else if p.points.len() > 1 {
                     p.adding = false;
                     p.points.remove(0);
                 } else {
                     p.adding = true;
-                    let mut x = random_pos(w as u32);
-                    let mut y = random_pos(w as u32);
+                    let rng = &mut self.rng;
+                    let mut x = random_pos(w as u32, rng);
+                    let mut y = random_pos(w as u32, rng);
                     while (x.powf(2.) + y.powf(2.)).sqrt() >= w / 2. {
-                        x = random_pos(w as u32);
-                        y = random_pos(w as u32);
+                        x = random_pos(w as u32, rng);
+                        y = random_pos(w as u32, rng);
                     }
                     p.points = vec![Point2::new(x, y)];
 
-                    p.max_len = random_range(L_MIN, L_MAX);
-                    p.color = self.palette.colors[random_range(0, self.palette.colors.len())];
-                    p.weight = random_range(10., 100.);
+                    p.max_len = rng.gen_range(L_MIN..L_MAX);
+                    p.color = self.palette.colors[rng.gen_range(0..self.palette.colors.len())];
+                    p.weight = rng.gen_range(10.0..100.0);
                 }
             }
             p.alpha = if p.adding || use_init {
@@ -123,7 +129,7 @@ impl Artwork for Model {
             draw_path(&draw, p);
             // draw.ellipse().xy(*p).radius(2.);
         }
-        self.flow.update(5., TAU as f64 * time, time);
+        self.flow.update(5., time);
     }
 
     fn get_model(&self) -> &BaseModel {
@@ -135,31 +141,30 @@ impl Artwork for Model {
     fn new(base: BaseModel) -> Model {
         let [w, _h] = base.texture.size();
         let palette = ColorPalette::new(4, PaletteType::Random, false);
-        let mut vec = vec![];
-        for _ in 0..N_LINES.pow(2) {
-            let x = random_pos(w);
-            let y = random_pos(w);
-            if (x.powf(2.) + y.powf(2.)).sqrt() < w as f32 / 2. {
-                vec.push(LinePath {
-                    points: vec![Point2::new(x, y)],
-                    max_len: random_range(L_MIN, L_MAX),
-                    adding: true,
-                    color: palette.colors[random_range(0, palette.colors.len())],
-                    weight: random_range(10., 100.),
-                    alpha: 0.1,
-                });
-            }
-        }
+        let mut rng = base.rng();
+        let lines = lines(w, &palette, &mut rng);
         let flow = GridFlow {
-            init_points: vec.clone(),
-            points: vec,
-            noise: noise::OpenSimplex::new(),
+            init_points: lines.clone(),
+            points: lines,
+            noise: LoopNoise::new(base.seed).radius(0.2),
+            noise_y: LoopNoise::new(base.seed).radius(0.2).center(2., -1.),
             width: w as f32,
             palette,
+            rng,
         };
         Model { base, flow }
     }
 
+    fn reseed(&mut self) {
+        let [w, _h] = self.base.texture.size();
+        let flow = &mut self.flow;
+        flow.rng = self.base.rng();
+        flow.points = lines(w, &flow.palette, &mut flow.rng);
+        flow.init_points = flow.points.clone();
+        flow.noise.reseed(self.base.seed);
+        flow.noise_y.reseed(self.base.seed);
+    }
+
     fn n_sec(&self) -> Option<u32> {
         Some(15)
     }
@@ -170,6 +175,7 @@ impl Artwork for Model {
             sample_per_frame: 1,
             shutter_angle: 0.3,
             extra_tex: None,
+            footage: None,
             noise_amount: 0.0,
         })
     }
@@ -178,40 +184,46 @@ impl Artwork for Model {
         match key {
             Key::P => {
                 self.flow.palette = ColorPalette::new(4, PaletteType::Random, false);
-                self.flow.points.iter_mut().for_each(|x| {
-                    x.color =
-                        self.flow.palette.colors[random_range(0, self.flow.palette.colors.len())]
-                })
+                let flow = &mut self.flow;
+                for line in flow.points.iter_mut() {
+                    line.color =
+                        flow.palette.colors[flow.rng.gen_range(0..flow.palette.colors.len())];
+                }
             }
             Key::R => {
-                let mut vec = vec![];
                 let [w, _h] = self.base.texture.size();
-                for _ in 0..N_LINES.pow(2) {
-                    let x = random_pos(w);
-                    let y = random_pos(w);
-                    if (x.powf(2.) + y.powf(2.)).sqrt() < w as f32 / 2. {
-                        vec.push(LinePath {
-                            points: vec![Point2::new(x, y)],
-                            max_len: random_range(L_MIN, L_MAX),
-                            adding: true,
-                            color: self.flow.palette.colors
-                                [random_range(0, self.flow.palette.colors.len())],
-                            weight: random_range(10., 100.),
-                            alpha: 0.1,
-                        });
-                    }
-                }
-                self.flow.init_points = vec.clone();
-                self.flow.points = vec;
+                let flow = &mut self.flow;
+                flow.points = lines(w, &flow.palette, &mut flow.rng);
+                flow.init_points = flow.points.clone();
             }
             __ => {}
         }
     }
 }
 
-fn random_pos(w: u32) -> f32 {
+/// Lines starting at random points of the disc of diameter `w`.
+fn lines(w: u32, palette: &ColorPalette, rng: &mut impl Rng) -> Vec<LinePath> {
+    let mut lines = vec![];
+    for _ in 0..N_LINES.pow(2) {
+        let x = random_pos(w, rng);
+        let y = random_pos(w, rng);
+        if (x.powf(2.) + y.powf(2.)).sqrt() < w as f32 / 2. {
+            lines.push(LinePath {
+                points: vec![Point2::new(x, y)],
+                max_len: rng.gen_range(L_MIN..L_MAX),
+                adding: true,
+                color: palette.colors[rng.gen_range(0..palette.colors.len())],
+                weight: rng.gen_range(10.0..100.0),
+                alpha: 0.1,
+            });
+        }
+    }
+    lines
+}
+
+fn random_pos(w: u32, rng: &mut impl Rng) -> f32 {
     map_range(
-        random::<f32>() * N_LINES as f32,
+        rng.gen::<f32>() * N_LINES as f32,
         0.,
         N_LINES as f32,
         -(w as f32) / 2.,
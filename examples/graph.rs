@@ -52,36 +52,52 @@ impl Artwork for Model {
     }
     fn new(base: BaseModel) -> Model {
         let [w, h] = base.texture.size();
-        let mut rng = rand::thread_rng();
-        let mut graph = <force_graph::ForceGraph>::new(Default::default());
-        let mut indices: Vec<force_graph::DefaultNodeIdx> = vec![];
-        for _ in 0..N_POINTS {
-            let is_anchor = rand::random::<f32>() > 0.9;
-            indices.push(graph.add_node(force_graph::NodeData {
-                x: (w as f32 / 5.) * (fmod(rng.gen::<f32>(), 1.) - 0.5),
-                y: (h as f32 / 5.) * (fmod(rng.gen::<f32>(), 1.) - 0.5),
-                is_anchor,
-                ..Default::default()
-            }));
-        }
-        indices.push(graph.add_node(force_graph::NodeData {
-            x: 0.,
-            y: 0.,
-            mass: 1000.,
-            is_anchor: true,
-            ..Default::default()
-        }));
-        for _ in 0..N_EDGES {
-            graph.add_edge(
-                indices[rng.gen_range(0..indices.len())],
-                indices[rng.gen_range(0..indices.len())],
-                Default::default(),
-            );
-        }
+        let (graph, node_indices) = graph(w, h, &mut base.rng());
         Model {
             base,
             graph,
-            node_indices: indices,
+            node_indices,
         }
     }
+
+    fn reseed(&mut self) {
+        let [w, h] = self.base.texture.size();
+        let (graph, node_indices) = graph(w, h, &mut self.base.rng());
+        self.graph = graph;
+        self.node_indices = node_indices;
+    }
+}
+
+/// A random graph, with a last anchored node moved across the canvas.
+fn graph(
+    w: u32,
+    h: u32,
+    rng: &mut impl Rng,
+) -> (force_graph::ForceGraph, Vec<force_graph::DefaultNodeIdx>) {
+    let mut graph = <force_graph::ForceGraph>::new(Default::default());
+    let mut indices: Vec<force_graph::DefaultNodeIdx> = vec![];
+    for _ in 0..N_POINTS {
+        let is_anchor = rng.gen::<f32>() > 0.9;
+        indices.push(graph.add_node(force_graph::NodeData {
+            x: (w as f32 / 5.) * (fmod(rng.gen::<f32>(), 1.) - 0.5),
+            y: (h as f32 / 5.) * (fmod(rng.gen::<f32>(), 1.) - 0.5),
+            is_anchor,
+            ..Default::default()
+        }));
+    }
+    indices.push(graph.add_node(force_graph::NodeData {
+        x: 0.,
+        y: 0.,
+        mass: 1000.,
+        is_anchor: true,
+        ..Default::default()
+    }));
+    for _ in 0..N_EDGES {
+        graph.add_edge(
+            indices[rng.gen_range(0..indices.len())],
+            indices[rng.gen_range(0..indices.len())],
+            Default::default(),
+        );
+    }
+    (graph, indices)
 }
@@ -1,6 +1,5 @@
 use artworks::{make_recorder_app, Artwork, BaseModel};
-use nannou::prelude::*;
-// use rand::Rng;
+use nannou::{prelude::*, rand::Rng};
 use rust_ca::{
     automaton::{Automaton, AutomatonImpl},
     rule::Rule,
@@ -23,10 +22,10 @@ const N_POINTS: usize = 50;
 impl Artwork for Model {
     fn draw_at_time(&mut self, _time: f64) {
         // First, reset the `draw` state.
-        let draw = &self.base.draw.scale(0.7);
-        draw.reset();
-        draw.background()
-            .color(srgba(0.08627, 0.08627, 0.08627, 1.));
+        self.base.draw.reset();
+        // The graph is drawn through `vector`, so it can be exported to SVG.
+        let draw = self.base.vector.scale(0.7);
+        draw.background(srgba(0.08627, 0.08627, 0.08627, 1.));
         let [w, h] = self.base.texture.size();
 
         let _seed = (self.base.seed % 1000) as f64 / 1000.;
@@ -99,10 +98,8 @@ impl Artwork for Model {
                 .color(srgba(1., 1., 1., *alpha))
                 .caps_round()
                 .weight(10.)
-                .points(
-                    Point2::new(pos[*s].0, pos[*s].1),
-                    Point2::new(pos[*e].0, pos[*e].1),
-                );
+                .start(Point2::new(pos[*s].0, pos[*s].1))
+                .end(Point2::new(pos[*e].0, pos[*e].1));
         }
         // if self.base.recording {
         self.graph.update(0.022);
@@ -201,20 +198,7 @@ impl Artwork for Model {
                 }
             }
         }
-        let mut ca = Automaton::new(2, N_POINTS, Rule::gol());
-        let o = N_POINTS.pow(2) / 2 + N_POINTS / 2;
-        ca[o] = 1;
-        ca[o - 1] = 1;
-        ca[o - 2] = 1;
-        ca[o - N_POINTS] = 1;
-        ca[o - 2 * N_POINTS - 1] = 1;
-
-        ca[o - 8] = 1;
-        ca[o - 9] = 1;
-        ca[o - 10] = 1;
-        ca[o - N_POINTS - 8] = 1;
-        ca[o - 2 * N_POINTS - 1 - 8] = 1;
-        ca.random_init();
+        let ca = automaton(&mut base.rng());
 
         Model {
             base,
@@ -224,4 +208,30 @@ impl Artwork for Model {
             counter: 0,
         }
     }
+
+    fn reseed(&mut self) {
+        self.ca = automaton(&mut self.base.rng());
+        self.counter = 0;
+    }
+}
+
+/// Random cells, with two gliders in the middle.
+fn automaton(rng: &mut impl Rng) -> Automaton {
+    let mut ca = Automaton::new(2, N_POINTS, Rule::gol());
+    for i in 0..N_POINTS.pow(2) {
+        ca[i] = rng.gen_range(0..2);
+    }
+    let o = N_POINTS.pow(2) / 2 + N_POINTS / 2;
+    ca[o] = 1;
+    ca[o - 1] = 1;
+    ca[o - 2] = 1;
+    ca[o - N_POINTS] = 1;
+    ca[o - 2 * N_POINTS - 1] = 1;
+
+    ca[o - 8] = 1;
+    ca[o - 9] = 1;
+    ca[o - 10] = 1;
+    ca[o - N_POINTS - 8] = 1;
+    ca[o - 2 * N_POINTS - 1 - 8] = 1;
+    ca
 }
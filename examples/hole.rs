@@ -1,4 +1,7 @@
-use artworks::{make_recorder_app, Artwork, BaseModel};
+use artworks::{
+    layers::{Clip, Layer, MaskKind},
+    make_recorder_app, Artwork, BaseModel,
+};
 use nannou::prelude::*;
 
 fn main() {
@@ -19,25 +22,22 @@ impl Artwork for Model {
 
         let _seed = (self.base.seed % 1000) as f64 / 1000.;
 
-        let centre = pt3(0.0, 0.0, 0.0);
-        let size = vec3(1.0, 1.0, 1.0);
-        let cuboid = geom::Cuboid::from_xyz_whd(centre, size);
-        let points = cuboid
-            .triangles_iter()
-            .flat_map(geom::Tri::vertices)
-            .map(|point| {
-                // Tex coords should be in range (0.0, 0.0) to (1.0, 1.0);
-                // This will have the logo show on the front and back faces.
-                point
-            });
-        let cube_side = w as f32 * 0.5;
-        draw.scale(cube_side)
-            .mesh()
-            .points(points)
-            .color(WHITE)
-            .z_radians(time as f32 * 0.33)
-            .x_radians(time as f32 * 0.166)
-            .y_radians(time as f32 * 0.25);
+        // The hole breathes over the loop. Inside of it, the colors of the
+        // cube and the background are swapped.
+        let layers = &self.base.layers;
+        let radius = w as f32 * (0.25 + 0.05 * (TAU * time as f32).sin());
+        layers.draw("hole").ellipse().radius(radius).color(WHITE);
+        let inside = layers.draw_inside("hole");
+        inside.ellipse().radius(radius).color(WHITE);
+        draw_cube(inside, w as f32 * 0.5, time, BLACK);
+        draw_cube(layers.draw("cube"), w as f32 * 0.5, time, WHITE);
+    }
+
+    fn layers() -> Vec<Layer> {
+        vec![
+            Layer::new("hole").as_mask(MaskKind::Alpha),
+            Layer::new("cube").mask("hole", Clip::Outside),
+        ]
     }
 
     fn get_model(&self) -> &BaseModel {
@@ -50,3 +50,17 @@ impl Artwork for Model {
         Model { base }
     }
 }
+
+fn draw_cube(draw: &Draw, side: f32, time: f64, color: Srgb<u8>) {
+    let centre = pt3(0.0, 0.0, 0.0);
+    let size = vec3(1.0, 1.0, 1.0);
+    let cuboid = geom::Cuboid::from_xyz_whd(centre, size);
+    let points = cuboid.triangles_iter().flat_map(geom::Tri::vertices);
+    draw.scale(side)
+        .mesh()
+        .points(points)
+        .color(color)
+        .z_radians(time as f32 * 0.33)
+        .x_radians(time as f32 * 0.166)
+        .y_radians(time as f32 * 0.25);
+}
@@ -1,8 +1,7 @@
-use artworks::{make_recorder_app, Artwork, BaseModel, Options};
+use artworks::{make_recorder_app, utils::noise::LoopNoise, Artwork, BaseModel, Options};
 use nannou::color::RgbHue;
 use nannou::ease::cubic::ease_out;
-use nannou::noise::{NoiseFn, OpenSimplex};
-use nannou::{noise, prelude::*};
+use nannou::{prelude::*, rand::Rng};
 
 fn main() {
     make_recorder_app::<Model>().run();
@@ -19,43 +18,29 @@ struct Item {
 struct Model {
     pub base: BaseModel,
     grid: Vec<Item>,
-    noise: OpenSimplex,
+    /// The noise of the radius of the items.
+    r_noise: LoopNoise,
+    /// The noises of the offsets of the items.
+    x_noise: LoopNoise,
+    y_noise: LoopNoise,
 }
 
 impl Artwork for Model {
     fn draw_at_time(&mut self, time: f64) {
         // First, reset the `draw` state.
-        let draw = &self.base.draw;
-        draw.reset();
+        self.base.draw.reset();
         // Get the width and height of the animation.
         let [w, _h] = self.base.texture.size();
-        let draw = draw.scale(0.85);
-        // Set the seed
-
-        let seed = (self.base.seed % 1000) as f64 / 1000.;
-        draw.background()
-            .color(srgba(0.08627, 0.08627, 0.08627, 1.));
+        // The ellipses are drawn through `vector`, so they can be exported to
+        // SVG.
+        let draw = self.base.vector.scale(0.85);
+        draw.background(srgba(0.08627, 0.08627, 0.08627, 1.));
         for item in &self.grid {
             let x = item.x * (2. * item.speed as f64 * time).exp() as f32;
             let y = item.y * (-2. * item.speed as f64 * time).exp() as f32;
-            let r_noise = self.noise.get([
-                (TAU_F64 * time).sin(),
-                (TAU_F64 * time).cos(),
-                seed,
-                item.sd as f64,
-            ]);
-            let x_noise = self.noise.get([
-                (TAU_F64 * time).sin() / 5.,
-                (TAU_F64 * time).cos() / 5.,
-                12. * seed,
-                item.sd as f64,
-            ]);
-            let y_noise = self.noise.get([
-                (TAU_F64 * time).sin() / 5.,
-                (TAU_F64 * time).cos() / 5.,
-                7. * seed,
-                item.sd as f64,
-            ]);
+            let r_noise = self.r_noise.get(item.sd as f64, 0., time);
+            let x_noise = self.x_noise.get(item.sd as f64, 0., time);
+            let y_noise = self.y_noise.get(item.sd as f64, 0., time);
             let rad = item.r * (1. + (r_noise as f32));
             let x = x + (w as f32 / 20.) * x_noise as f32;
             let y = y + (w as f32 / 20.) * y_noise as f32;
@@ -99,6 +84,7 @@ impl Artwork for Model {
     //         sample_per_frame: 5,
     //         shutter_angle: 1.,
     //         extra_tex: None,
+    //         footage: None,
     //         noise_amount: 0.0,
     //     })
     // }
@@ -114,34 +100,48 @@ impl Artwork for Model {
     }
     fn new(base: BaseModel) -> Model {
         let [w, _h] = base.texture.size();
-        let mut grid_vec = vec![];
-        let grid = 20;
-        for i in -grid..2 * grid {
-            for j in -grid..2 * grid {
-                let item = Item {
-                    x: (2. * random_f32() - 1.) * w as f32,
-                    y: (2. * random_f32() - 1.) * w as f32,
-                    r: random_f32() * 6. + 2.,
-                    sd: random(),
-                    speed: 0.8 + 4.5 * random_f32(),
-                };
-                for i in -15..=15 {
-                    grid_vec.push(Item {
-                        x: item.x * (-(i as f32) * item.speed as f32).exp(),
-                        y: item.y * (i as f32 * item.speed as f32).exp(),
-                        r: item.r,
-                        sd: item.sd,
-                        speed: item.speed,
-                    });
-                }
-                grid_vec.push(item);
-            }
-        }
-        let noise = OpenSimplex::new();
+        let grid = grid(w, &mut base.rng());
         Model {
+            r_noise: LoopNoise::new(base.seed),
+            x_noise: LoopNoise::new(base.seed).radius(0.2).center(12., 0.),
+            y_noise: LoopNoise::new(base.seed).radius(0.2).center(7., 0.),
             base,
-            grid: grid_vec,
-            noise,
+            grid,
+        }
+    }
+
+    fn reseed(&mut self) {
+        let [w, _h] = self.base.texture.size();
+        self.grid = grid(w, &mut self.base.rng());
+        self.r_noise.reseed(self.base.seed);
+        self.x_noise.reseed(self.base.seed);
+        self.y_noise.reseed(self.base.seed);
+    }
+}
+
+fn grid(w: u32, rng: &mut impl Rng) -> Vec<Item> {
+    let mut grid_vec = vec![];
+    let grid = 20;
+    for _ in -grid..2 * grid {
+        for _ in -grid..2 * grid {
+            let item = Item {
+                x: (2. * rng.gen::<f32>() - 1.) * w as f32,
+                y: (2. * rng.gen::<f32>() - 1.) * w as f32,
+                r: rng.gen::<f32>() * 6. + 2.,
+                sd: rng.gen(),
+                speed: 0.8 + 4.5 * rng.gen::<f32>(),
+            };
+            for i in -15..=15 {
+                grid_vec.push(Item {
+                    x: item.x * (-(i as f32) * item.speed as f32).exp(),
+                    y: item.y * (i as f32 * item.speed as f32).exp(),
+                    r: item.r,
+                    sd: item.sd,
+                    speed: item.speed,
+                });
+            }
+            grid_vec.push(item);
         }
     }
+    grid_vec
 }
@@ -1,9 +1,9 @@
-use artworks::{make_recorder_app, Artwork, BaseModel, Options};
+use artworks::{make_recorder_app, utils::noise::LoopNoise, Artwork, BaseModel, Options};
 
 use nannou::{
     ease::cubic::{ease_in, ease_out},
-    noise::{NoiseFn, OpenSimplex},
     prelude::*,
+    rand::Rng,
 };
 
 fn main() {
@@ -13,6 +13,10 @@ fn main() {
 struct Model {
     pub base: BaseModel,
     balls: Vec<Ball>,
+    /// The steps of the balls along their rectangles.
+    noise: LoopNoise,
+    /// The depth of the balls, which sets their shade.
+    depth_noise: LoopNoise,
 }
 
 struct Ball {
@@ -73,22 +77,14 @@ impl Artwork for Model {
 
         // Get the width and height of the animation.
         let [w, _h] = self.base.texture.size();
-        // Set the seed
-        let seed = (self.base.seed % 1000) as f64 / 1000.;
         draw.background()
             .color(srgba(0.08627, 0.08627, 0.08627, 1.));
         let draw = draw.scale(0.6);
-        let ns = OpenSimplex::new();
         let total = 1. + 0.2 * (TAU as f64 * time).sin();
         let fact = (0.7 + 0.3 * (TAU as f64 * time).cos()) as f32;
         for ball in self.balls.iter() {
             let ltime = (2. * (time - ball.offset + 1.)).fract();
-            let rs = ns.get([
-                seed,
-                ball.offset_ax as f64,
-                (TAU as f64 * ltime).sin(),
-                (TAU as f64 * ltime).cos(),
-            ]);
+            let rs = self.noise.get(ball.offset_ax as f64, 0., ltime);
             let step_low = map_range(fact, 0.4, 1., 0.13, 0.18);
             let step_high = map_range(fact, 0.4, 1., 0.37, 0.32);
             let steps = map_range(rs, -1., 1., step_low, step_high);
@@ -103,12 +99,9 @@ impl Artwork for Model {
             );
 
             let z = 10.
-                * ns.get([
-                    seed - 100.,
-                    100. * ball.offset_ax as f64,
-                    (TAU as f64 * ltime).sin(),
-                    (TAU as f64 * ltime).cos(),
-                ]) as f32;
+                * self
+                    .depth_noise
+                    .get(100. * ball.offset_ax as f64, 0., ltime) as f32;
             let col_val = map_range(z, -10., 7., 0.2, 1.);
             let col = srgba(col_val, col_val, col_val, 0.9);
 
@@ -133,6 +126,7 @@ impl Artwork for Model {
             sample_per_frame: 5,
             shutter_angle: 0.5,
             extra_tex: None,
+            footage: None,
             noise_amount: 0.0,
         })
     }
@@ -148,15 +142,29 @@ impl Artwork for Model {
     }
     fn new(base: BaseModel) -> Model {
         let [w, _h] = base.texture.size();
+        let balls = balls(w, &mut base.rng());
         Model {
+            noise: LoopNoise::new(base.seed),
+            depth_noise: LoopNoise::new(base.seed).center(-100., 0.),
             base,
-            balls: (0..400)
-                .map(|_| Ball {
-                    size: 5. + 10. * random_f32(),
-                    offset: random_f64(),
-                    offset_ax: (w as f32 / 50.) * (2. * random_f32() - 1.),
-                })
-                .collect(),
+            balls,
         }
     }
+
+    fn reseed(&mut self) {
+        let [w, _h] = self.base.texture.size();
+        self.balls = balls(w, &mut self.base.rng());
+        self.noise.reseed(self.base.seed);
+        self.depth_noise.reseed(self.base.seed);
+    }
+}
+
+fn balls(w: u32, rng: &mut impl Rng) -> Vec<Ball> {
+    (0..400)
+        .map(|_| Ball {
+            size: 5. + 10. * rng.gen::<f32>(),
+            offset: rng.gen::<f64>(),
+            offset_ax: (w as f32 / 50.) * (2. * rng.gen::<f32>() - 1.),
+        })
+        .collect()
 }
@@ -1,4 +1,7 @@
-use artworks::{make_recorder_app, Artwork, BaseModel, Options};
+use artworks::{
+    assets::{self, Handle},
+    make_recorder_app, Artwork, BaseModel, Options,
+};
 use nannou::{
     color::Gradient,
     color::Srgb,
@@ -6,6 +9,7 @@ use nannou::{
     noise,
     noise::{NoiseFn, OpenSimplex},
     prelude::*,
+    rand::Rng,
     text::Font,
 };
 
@@ -20,6 +24,11 @@ struct Model {
     shoot_star_pos: Vec<(Vec2, Srgb, Vec2, f32)>,
     rand: Vec<f32>,
     gal_coords: Vec<(f32, f32, f32, i32)>,
+    sky: Option<Handle<assets::Image>>,
+    halo: Option<Handle<assets::Image>>,
+    road: Option<Handle<assets::Image>>,
+    /// The font of the title, drawn with the default font when missing.
+    font: Option<Handle<assets::Font>>,
 }
 
 const N_CIRCLES: usize = 8;
@@ -58,10 +67,12 @@ impl Artwork for Model {
             (Point2::new(1., 1.), (1., 1.)),
             (Point2::new(1., -1.), (1., 0.)),
         ];
-        draw.translate(Vec3::new(0., 0., -800.))
-            .scale(w as f32 / 2.)
-            .polygon()
-            .points_textured(&self.base.extra_tex.as_ref().unwrap()[0], points);
+        if let Some(sky) = self.sky {
+            draw.translate(Vec3::new(0., 0., -800.))
+                .scale(w as f32 / 2.)
+                .polygon()
+                .points_textured(&self.base.assets.texture(sky), points);
+        }
 
         // Stars
         for (s, off, c) in self.star_pos.iter() {
@@ -159,10 +170,12 @@ impl Artwork for Model {
             let points = [(0., 0.), (0., 1.), (1., 1.), (1., 0.)];
 
             let col: Srgba = srgba(255. / 255., 255. / 255., 255. / 255., alpha);
-            draw.polygon().color(col).points_textured(
-                &self.base.extra_tex.as_ref().unwrap()[2],
-                quad.clone().into_iter().zip(points.into_iter()),
-            );
+            if let Some(road) = self.road {
+                draw.polygon().color(col).points_textured(
+                    &self.base.assets.texture(road),
+                    quad.clone().into_iter().zip(points.into_iter()),
+                );
+            }
             draw.polygon().color(col).points(quad);
         }
         let n_tiles = N_LINES * 5;
@@ -203,61 +216,8 @@ impl Artwork for Model {
                 .color(col)
                 .points(quad[1], quad[2]);
         }
-        make_text(draw, w as f32, h as f32);
-    }
-
-    fn key_pressed(&mut self, _app: &App, key: Key) {
-        match key {
-            Key::S => {
-                let os = noise::OpenSimplex::new();
-                let cols = [
-                    srgb(1., 1., 1.),
-                    srgb(1., 1., 0.),
-                    srgb(1., 1., 96. / 255.),
-                    srgb(1., 1., 146. / 255.),
-                    srgb(1., 147. / 255., 0.),
-                ];
-                let stars = (0..N_STARS)
-                    .map(|_| {
-                        (
-                            Vec2::new(random_range(0., 1.), random_range(0., 1.)),
-                            random_range::<f32>(0., 1.),
-                            cols[random_range(0, cols.len())],
-                        )
-                    })
-                    .collect();
-                let shoot_stars = (0..N_STARS / 8)
-                    .map(|_| {
-                        let mut speed_vec =
-                            Vec2::new(random_range(-5., 5.), random_range(-1., 1.)).normalize();
-                        speed_vec *= random_range(5., 10.);
-                        (
-                            Vec2::new(random_range(0., 1.), random_range(0., 1.)),
-                            cols[random_range(0, cols.len())],
-                            speed_vec,
-                            random_range::<f32>(0., 1.),
-                        )
-                    })
-                    .collect();
-                let rand = (0..N_GAL * N_BR).map(|_| random_range(-1., 1.)).collect();
-                let gs = (0..N_GALS)
-                    .map(|_| {
-                        let base_x = random_range(-1., 1.);
-                        let base_y = random_range(-1., 1.);
-                        let scale = random_range(0.1, 1.);
-                        let speed = random_range(-2, 3);
-
-                        (base_x, base_y, scale, speed)
-                    })
-                    .collect();
-                self.os = os;
-                self.star_pos = stars;
-                self.rand = rand;
-                self.gal_coords = gs;
-                self.shoot_star_pos = shoot_stars;
-            }
-            _ => {}
-        }
+        let font = self.font.map(|f| self.base.assets.font(f));
+        make_text(draw, w as f32, h as f32, font);
     }
 
     fn get_model(&self) -> &BaseModel {
@@ -272,8 +232,44 @@ impl Artwork for Model {
         Some(11)
     }
 
-    fn new(base: BaseModel) -> Model {
+    fn new(mut base: BaseModel) -> Model {
         let os = noise::OpenSimplex::new();
+        // Missing assets are reported, and what they texture is not drawn.
+        let assets = &mut base.assets;
+        let sky = assets
+            .load_image("tst.jpg")
+            .map_err(|e| eprintln!("{}", e))
+            .ok();
+        let halo = assets
+            .load_image("halo.png")
+            .map_err(|e| eprintln!("{}", e))
+            .ok();
+        let road = assets
+            .load_image("road.jpg")
+            .map_err(|e| eprintln!("{}", e))
+            .ok();
+        let font = assets
+            .load_font("space age.ttf")
+            .map_err(|e| eprintln!("{}", e))
+            .ok();
+        let mut model = Model {
+            base,
+            os,
+            star_pos: vec![],
+            rand: vec![],
+            gal_coords: vec![],
+            shoot_star_pos: vec![],
+            sky,
+            halo,
+            road,
+            font,
+        };
+        model.reseed();
+        model
+    }
+
+    fn reseed(&mut self) {
+        let mut rng = self.base.rng();
         let cols = [
             srgb(1., 1., 1.),
             srgb(1., 1., 0.),
@@ -284,47 +280,45 @@ impl Artwork for Model {
         let stars = (0..N_STARS)
             .map(|_| {
                 (
-                    Vec2::new(random_range(0., 1.), random_range(0., 1.)),
-                    random_range::<f32>(0., 1.),
-                    cols[random_range(0, cols.len())],
+                    Vec2::new(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)),
+                    rng.gen_range(0.0..1.0),
+                    cols[rng.gen_range(0..cols.len())],
                 )
             })
             .collect();
         let shoot_stars = (0..N_STARS / 8)
             .map(|_| {
-                let mut speed_vec = Vec2::new(random_range(-5., 5.), random_range(-0.2, 0.8));
+                let mut speed_vec = Vec2::new(rng.gen_range(-5.0..5.0), rng.gen_range(-0.2..0.8));
                 while speed_vec.x.abs() < 2. {
-                    speed_vec.x = random_range(-5., 5.);
+                    speed_vec.x = rng.gen_range(-5.0..5.0);
                 }
                 speed_vec = speed_vec.normalize();
-                speed_vec *= random_range(5., 15.);
+                speed_vec *= rng.gen_range(5.0..15.0);
                 (
-                    Vec2::new(random_range(0., 1.), random_range(0., 1.)),
-                    cols[random_range(0, cols.len())],
+                    Vec2::new(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)),
+                    cols[rng.gen_range(0..cols.len())],
                     speed_vec,
-                    random_range::<f32>(0., 1.),
+                    rng.gen_range(0.0..1.0),
                 )
             })
             .collect();
-        let rand = (0..N_GAL * N_BR).map(|_| random_range(-1., 1.)).collect();
+        let rand = (0..N_GAL * N_BR)
+            .map(|_| rng.gen_range(-1.0..1.0))
+            .collect();
         let gs = (0..N_GALS)
             .map(|_| {
-                let base_x = random_range(-1., 1.);
-                let base_y = random_range(-1., 1.);
-                let scale = random_range(0.1, 1.);
-                let speed = random_range(-2, 3);
+                let base_x = rng.gen_range(-1.0..1.0);
+                let base_y = rng.gen_range(-1.0..1.0);
+                let scale = rng.gen_range(0.1..1.0);
+                let speed = rng.gen_range(-2..3);
 
                 (base_x, base_y, scale, speed)
             })
             .collect();
-        Model {
-            base,
-            os,
-            star_pos: stars,
-            rand,
-            gal_coords: gs,
-            shoot_star_pos: shoot_stars,
-        }
+        self.star_pos = stars;
+        self.rand = rand;
+        self.gal_coords = gs;
+        self.shoot_star_pos = shoot_stars;
     }
 
     fn get_options() -> Option<Options> {
@@ -332,11 +326,8 @@ impl Artwork for Model {
             chroma: 0.5,
             sample_per_frame: 10,
             shutter_angle: 0.5,
-            extra_tex: Some(vec![
-                "tst.jpg".to_string(),
-                "halo.png".to_string(),
-                "road.jpg".to_string(),
-            ]),
+            extra_tex: None,
+            footage: None,
             noise_amount: 0.01,
         })
     }
@@ -503,12 +494,14 @@ impl Model {
                     .map(|(p1, p2)| (trans.project_point3(p1), p2));
                 let col: Srgba = srgba(0., 0., 0., alpha);
 
-                draw.path()
-                    .stroke()
-                    .caps_round()
-                    .color(col)
-                    .stroke_weight(20.)
-                    .points_textured(&self.base.extra_tex.as_ref().unwrap()[1], points.clone());
+                if let Some(halo) = self.halo {
+                    draw.path()
+                        .stroke()
+                        .caps_round()
+                        .color(col)
+                        .stroke_weight(20.)
+                        .points_textured(&self.base.assets.texture(halo), points.clone());
+                }
                 draw.path()
                     .stroke()
                     .caps_round()
@@ -520,7 +513,7 @@ impl Model {
     }
 }
 
-fn make_text(draw: &Draw, w: f32, h: f32) {
+fn make_text(draw: &Draw, w: f32, h: f32, font: Option<&Font>) {
     let bbox_w = w / 2.3;
     let x1 = -700.;
     let y_off = -160.;
@@ -528,8 +521,9 @@ fn make_text(draw: &Draw, w: f32, h: f32) {
     let f_size = 90;
     let y = -0.8 * w / 3.;
 
-    let font_data: &[u8] = include_bytes!("/Users/hugo/Library/Fonts/space age.ttf");
-    let font: Font = Font::from_bytes(font_data).unwrap();
+    let font = font
+        .cloned()
+        .unwrap_or_else(nannou::text::font::default_notosans);
 
     let rect = Rect::from_x_y_w_h(x1, y, bbox_w, h / 2.).pad(20.);
     draw.text("Bruni")
@@ -1,11 +1,16 @@
-use artworks::{make_recorder_app, Artwork, BaseModel, Options};
+use artworks::{
+    make_recorder_app,
+    utils::{easing::Easing, timeline::Timeline},
+    Artwork, BaseModel, Options,
+};
 use audrey::dasp_frame::Frame;
 use nannou::{
     ease::{
-        cubic::{ease_in, ease_in_out, ease_out},
+        cubic::{ease_in, ease_in_out},
         map_clamp,
     },
     prelude::*,
+    rand::Rng,
 };
 
 fn main() {
@@ -14,6 +19,7 @@ fn main() {
 
 struct Model {
     pub base: BaseModel,
+    timeline: Timeline,
     translatesx: Vec<f32>,
     translatesy: Vec<f32>,
     rotates: Vec<f32>,
@@ -35,10 +41,9 @@ impl Artwork for Model {
         let _seed = (self.base.seed % 1000) as f64 / 1000.;
         draw.background()
             .color(srgba(0.08627, 0.08627, 0.08627, 1.));
-        let chapters = [0.2, 0.4, 0.5, 0.8];
-        if time < chapters[0] {
-            let local_time = (time / chapters[0]) as f32;
-            let local_time = ease_out(local_time, 0., 1., 1.);
+        let timeline = &self.timeline;
+        if timeline.is_active("arrival", time) {
+            let local_time = timeline.progress("arrival", time);
             draw.ellipse()
                 .color(srgba(219. / 255., 245. / 255., 137. / 255., 1.))
                 .radius(w as f32 / 22.)
@@ -54,16 +59,15 @@ impl Artwork for Model {
                     -(1. - local_time) * (w as f32),
                 );
         }
-        if time >= chapters[0] && time < chapters[1] {
-            let local_time = ((time - chapters[0]) / (chapters[1] - chapters[0])) as f32;
-            let alpha = ease_in(local_time, 0., 1., 1.);
+        if timeline.is_active("merge", time) {
+            let local_time = timeline.local_time("merge", time);
+            let alpha = timeline.progress("merge", time);
             draw.ellipse()
                 .color(srgba(239. / 255., 225. / 255., 197. / 255., 1. - alpha))
                 .radius((1. + 11. * local_time) * w as f32 / 12.)
                 .x_y(0., 0.);
         }
-        if time >= chapters[1] && time < chapters[2] {
-            let local_time = ((time - chapters[1]) / (chapters[2] - chapters[1])) as f32;
+        if timeline.is_active("rects", time) {
             for c in 0..N_RECT {
                 draw.rect()
                     .w_h(100., 100.)
@@ -71,12 +75,8 @@ impl Artwork for Model {
                     .x_y(0., 0.);
             }
         }
-        if time >= chapters[2] && time < chapters[3] {
-            let local_time = clamp(
-                ((time - chapters[2]) / (chapters[3] - chapters[2])) as f32,
-                0.,
-                1.0,
-            );
+        if timeline.is_active("spread", time) {
+            let local_time = timeline.local_time("spread", time);
 
             for c in 0..=N_RECT {
                 draw.translate(Vec3::new(
@@ -133,8 +133,8 @@ impl Artwork for Model {
                 .x_y(0., 0.);
             }
         }
-        if time >= chapters[3] {
-            let local_time = clamp(((time - chapters[3]) / (1. - chapters[3])) as f32, 0., 1.0);
+        if timeline.is_active("departure", time) {
+            let local_time = timeline.local_time("departure", time);
 
             for c in 0..=N_RECT {
                 let c_time = clamp(
@@ -209,82 +209,55 @@ impl Artwork for Model {
         &mut self.base
     }
     fn new(base: BaseModel) -> Model {
-        let [w, _h] = base.texture.size();
-        Model {
+        let mut model = Model {
             base,
-            translatesx: (0..=N_RECT)
-                .map(|c| {
-                    let tr = map_range(c, 0, N_RECT, -(w as f32) / 3., w as f32 / 3.);
-                    tr
-                })
-                .collect(),
-            translatesy: (0..=N_RECT)
-                .map(|c| {
-                    let tr = map_range(c, 0, N_RECT, -(w as f32) / 3., w as f32 / 3.)
-                        + (w as f32 / 12.) * (random_f32() * 2. - 1.);
-                    tr
-                })
-                .collect(),
-            rotates: (0..=N_RECT)
-                .map(|c| -TAU * 45. / 360. + (2. * random_f32() - 1.) / 5.)
-                .collect(),
-            heights: (0..=N_RECT)
-                .map(|c| (1. + 0.2 * (2. * random_f32() - 1.)) * w as f32 / 10.)
-                .collect(),
-            widths: (0..=N_RECT)
-                .map(|c| {
-                    let abs = map_range(
-                        (c as f32 - N_RECT as f32 / 2.).abs(),
-                        0.,
-                        N_RECT as f32 / 2.,
-                        2.,
-                        6.,
-                    );
-                    (1. + 0.8 * (2. * random_f32() - 1.)) * w as f32 / abs
-                })
-                .collect(),
-        }
+            timeline: Timeline::new()
+                .then("arrival", 0.2)
+                .eased(Easing::CubicOut)
+                .then("merge", 0.2)
+                .eased(Easing::CubicIn)
+                .then("rects", 0.1)
+                .then("spread", 0.3)
+                .then("departure", 0.2),
+            translatesx: vec![],
+            translatesy: vec![],
+            rotates: vec![],
+            heights: vec![],
+            widths: vec![],
+        };
+        model.reseed();
+        model
     }
-    fn key_pressed(&mut self, _app: &App, key: Key) {
-        match key {
-            Key::S => {
-                let [w, _h] = self.base.texture.size();
-                self.translatesx = (0..=N_RECT)
-                    .map(|c| {
-                        let tr = map_range(c, 0, N_RECT, -(w as f32) / 3., w as f32 / 3.)
-                            + (w as f32 / 12.) * (random_f32() * 2. - 1.);
-                        tr
-                    })
-                    .collect();
-                self.translatesy = (0..=N_RECT)
-                    .map(|c| {
-                        let tr = map_range(c, 0, N_RECT, -(w as f32) / 3., w as f32 / 3.)
-                            + (w as f32 / 12.) * (random_f32() * 2. - 1.);
-                        tr
-                    })
-                    .collect();
-                self.rotates = (0..=N_RECT)
-                    .map(|c| -TAU * 45. / 360. + (2. * random_f32() - 1.) / 5.)
-                    .collect();
-                self.heights = (0..=N_RECT)
-                    .map(|c| (1. + 0.2 * (2. * random_f32() - 1.)) * w as f32 / 10.)
-                    .collect();
-                self.widths = (0..=N_RECT)
-                    .map(|c| {
-                        let abs = map_range(
-                            (c as f32 - N_RECT as f32 / 2.).abs(),
-                            0.,
-                            N_RECT as f32 / 2.,
-                            2.,
-                            6.,
-                        );
-                        (1. + 0.8 * (2. * random_f32() - 1.)) * w as f32 / abs
-                    })
-                    .collect();
-            }
-
-            _ => {}
-        }
+    fn reseed(&mut self) {
+        let [w, _h] = self.base.texture.size();
+        let mut rng = self.base.rng();
+        self.translatesx = (0..=N_RECT)
+            .map(|c| map_range(c, 0, N_RECT, -(w as f32) / 3., w as f32 / 3.))
+            .collect();
+        self.translatesy = (0..=N_RECT)
+            .map(|c| {
+                map_range(c, 0, N_RECT, -(w as f32) / 3., w as f32 / 3.)
+                    + (w as f32 / 12.) * (rng.gen::<f32>() * 2. - 1.)
+            })
+            .collect();
+        self.rotates = (0..=N_RECT)
+            .map(|_| -TAU * 45. / 360. + (rng.gen::<f32>() * 2. - 1.) / 5.)
+            .collect();
+        self.heights = (0..=N_RECT)
+            .map(|_| (1. + 0.2 * (rng.gen::<f32>() * 2. - 1.)) * w as f32 / 10.)
+            .collect();
+        self.widths = (0..=N_RECT)
+            .map(|c| {
+                let abs = map_range(
+                    (c as f32 - N_RECT as f32 / 2.).abs(),
+                    0.,
+                    N_RECT as f32 / 2.,
+                    2.,
+                    6.,
+                );
+                (1. + 0.8 * (rng.gen::<f32>() * 2. - 1.)) * w as f32 / abs
+            })
+            .collect();
     }
     fn get_options() -> Option<Options> {
         Some(Options {
@@ -292,6 +265,7 @@ impl Artwork for Model {
             sample_per_frame: 6,
             shutter_angle: 1.,
             extra_tex: None,
+            footage: None,
             noise_amount: 0.1,
         })
     }
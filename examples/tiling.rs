@@ -1,7 +1,8 @@
-use artworks::{make_recorder_app, Artwork, BaseModel, Options};
+use artworks::{make_recorder_app, svg::VectorDraw, Artwork, BaseModel, Options};
 use nannou::{
     ease::{elastic::ease_out, map_clamp},
     prelude::*,
+    rand::Rng,
 };
 
 fn main() {
@@ -16,15 +17,15 @@ struct Model {
 impl Artwork for Model {
     fn draw_at_time(&mut self, time: f64) {
         // First, reset the `draw` state.
-        let draw = &self.base.draw;
-        draw.reset();
+        self.base.draw.reset();
+        // The tiles are drawn through `vector`, so they can be exported to SVG.
+        let draw = &self.base.vector;
 
         // Get the width and height of the animation.
         let [w, _h] = self.base.texture.size();
         // Set the seed
         let _seed = (self.base.seed % 1000) as f64 / 1000.;
-        draw.background()
-            .color(srgba(0.08627, 0.08627, 0.08627, 1.));
+        draw.background(srgba(0.08627, 0.08627, 0.08627, 1.));
         let size = w as f32 / 20.;
         let side = (3.).sqrt() * size;
         let height = (3. / 2.) * size;
@@ -91,23 +92,12 @@ impl Artwork for Model {
         &mut self.base
     }
     fn new(base: BaseModel) -> Model {
-        Model {
-            base,
-            random_ts: (-5..5)
-                .map(|_| {
-                    (-5..5)
-                        .map(|_| {
-                            let t = random_f64();
-                            if t < 0.7 {
-                                Some(t)
-                            } else {
-                                None
-                            }
-                        })
-                        .collect()
-                })
-                .collect(),
-        }
+        let random_ts = random_ts(&mut base.rng());
+        Model { base, random_ts }
+    }
+
+    fn reseed(&mut self) {
+        self.random_ts = random_ts(&mut self.base.rng());
     }
 
     fn get_options() -> Option<Options> {
@@ -116,12 +106,31 @@ impl Artwork for Model {
             sample_per_frame: 5,
             shutter_angle: 1.,
             extra_tex: None,
+            footage: None,
             noise_amount: 0.0,
         })
     }
 }
 
-fn draw_pent(draw: &Draw, size: f32, f: f32, color: Srgba) {
+/// The rotation times of the tiles, or `None` for the tiles that stay still.
+fn random_ts(rng: &mut impl Rng) -> Vec<Vec<Option<f64>>> {
+    (-5..5)
+        .map(|_| {
+            (-5..5)
+                .map(|_| {
+                    let t = rng.gen::<f64>();
+                    if t < 0.7 {
+                        Some(t)
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn draw_pent(draw: &VectorDraw, size: f32, f: f32, color: Srgba) {
     let p0 = Vec2::new(0., size);
     let thet = PI / 2. + TAU / 3.;
     let p1 = Vec2::new(size * thet.cos(), size * thet.sin());
@@ -140,7 +149,7 @@ fn draw_pent(draw: &Draw, size: f32, f: f32, color: Srgba) {
         .points([p0, p1a, p1b, p2a, p2b]);
 }
 
-fn draw_block(draw: &Draw, size: f32, f: f32, palette: (Srgba, Srgba)) {
+fn draw_block(draw: &VectorDraw, size: f32, f: f32, palette: (Srgba, Srgba)) {
     draw_pent(
         &draw.translate(Vec3::new(0., -size, 0.)),
         size,
@@ -1,5 +1,5 @@
-use artworks::{make_recorder_app, Artwork, BaseModel, Options};
-use nannou::{noise::NoiseFn, prelude::*};
+use artworks::{make_recorder_app, utils::noise::LoopNoise, Artwork, BaseModel, Options};
+use nannou::prelude::*;
 
 fn main() {
     make_recorder_app::<Model>().run();
@@ -7,6 +7,10 @@ fn main() {
 
 struct Model {
     pub base: BaseModel,
+    /// The width of the rings.
+    noise: LoopNoise,
+    /// The speed of the points around the rings.
+    speed_noise: LoopNoise,
     // pt_offsets: Vec<u32>,
 }
 
@@ -22,11 +26,10 @@ impl Artwork for Model {
         let bg = srgba(0.08627, 0.08627, 0.08627, 1.);
         draw.background().color(bg);
         let draw = draw.x_degrees(30.);
-        let seed = (self.base.seed % 1000) as f64 / 1000.;
         let amp = 5;
         let div = 20.;
         let t_mut = 30;
-        let noise = nannou::noise::OpenSimplex::new();
+        let (noise, speed_noise) = (&self.noise, &self.speed_noise);
         for k in -amp - 1..=amp + 1 {
             let ratk = (-3. * time as f32 + k as f32) / amp as f32;
             for i in -t_mut - 50..N_CIRC + t_mut + 50 {
@@ -36,12 +39,7 @@ impl Artwork for Model {
                 let rat_faster = (arrow * t_mut as f32 * time as f32 + i as f32) / N_CIRC as f32;
                 let add = 0.01
                     + map_range(
-                        noise.get([
-                            rat_faster as f64,
-                            ratk as f64 / 2.,
-                            0.4 * (TAU as f64 * time).cos() + seed / 2.3,
-                            0.4 * (TAU as f64 * time).sin() + seed,
-                        ]) as f32,
+                        noise.get(rat_faster as f64, ratk as f64 / 2., time) as f32,
                         -1.,
                         1.,
                         0.,
@@ -61,12 +59,7 @@ impl Artwork for Model {
                     let msk = 200.;
                     let mult = 8.
                         * map_range(
-                            noise.get([
-                                rat_faster as f64,
-                                ratk as f64,
-                                0.4 * (TAU as f64 * time).cos() + seed / 3.3,
-                                0.4 * (TAU as f64 * time).sin() + 2. * seed,
-                            ]) as f32,
+                            speed_noise.get(rat_faster as f64, ratk as f64, time) as f32,
                             -1.,
                             1.,
                             0.2,
@@ -111,17 +104,25 @@ impl Artwork for Model {
             sample_per_frame: 5,
             shutter_angle: 0.8,
             extra_tex: None,
+            footage: None,
             noise_amount: 0.0,
         })
     }
     fn new(base: BaseModel) -> Model {
         // let pt_offsets = (0..=C_DET).map(|_| random_range::<u32>(2, 8)).collect();
         Model {
+            noise: LoopNoise::new(base.seed).radius(0.4),
+            speed_noise: LoopNoise::new(base.seed).radius(0.4).center(2., -1.),
             base,
             // pt_offsets
         }
     }
 
+    fn reseed(&mut self) {
+        self.noise.reseed(self.base.seed);
+        self.speed_noise.reseed(self.base.seed);
+    }
+
     fn n_sec(&self) -> Option<u32> {
         Some(5)
     }
@@ -0,0 +1,42 @@
+# Render frames of artworks at a fixed seed and compare them with their
+# reference images in tests/golden/.
+#
+# Usage: ./golden_test.sh [--update] [NAME...]
+#
+# Without names, every example is tested. Artworks without references are
+# skipped, so the test only fails on frames that changed. With --update, the
+# references of the given artworks are written instead.
+FLAG=--golden
+if [ "$1" = "--update" ]; then
+    FLAG=--golden-update
+    shift
+    if [ -z "$1" ]; then
+        echo "Give the names of the artworks to update" >&2
+        exit 1
+    fi
+fi
+cd "$(dirname "$0")" || exit 1
+NAMES="$@"
+if [ -z "$NAMES" ]; then
+    NAMES=$(ls examples | sed -n 's/\.rs$//p')
+fi
+
+FAILED=""
+SKIPPED=""
+for NAME in $NAMES; do
+    if [ "$FLAG" = "--golden" ] && ! ls tests/golden/$NAME/*.png > /dev/null 2>&1; then
+        SKIPPED="$SKIPPED $NAME"
+        continue
+    fi
+    echo "== $NAME"
+    cargo run --release --example $NAME -- $FLAG || FAILED="$FAILED $NAME"
+done
+
+if [ -n "$SKIPPED" ]; then
+    echo "No references, skipped:$SKIPPED"
+    echo "Write them with ./golden_test.sh --update NAME..."
+fi
+if [ -n "$FAILED" ]; then
+    echo "Failed:$FAILED" >&2
+    exit 1
+fi
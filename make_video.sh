@@ -1,5 +1,38 @@
+# Record an artwork and encode its frames to a video in videos/.
+#
+# Usage: ./make_video.sh NAME [AUDIO [OFFSET [FADE_IN [FADE_OUT]]]]
+#
+# When an audio file is given, it is muxed into the video starting OFFSET
+# seconds into the file. The audio is looped if it is shorter than the
+# animation and trimmed to its length, with optional fades in and out lasting
+# FADE_IN and FADE_OUT seconds.
 mkdir -p videos
 TSTAMP=$(date "+%Y-%m-%d-%H-%M-%S")
-cargo run --release --example $1 && \
-    ffmpeg -y -r 60 -i $1/%d.png -c:v libx264 -vf "fps=60,format=yuv420p" "videos/$TSTAMP-$1.mp4" && \
-    rm -rf $1
+AUDIO=$2
+OFFSET=${3:-0}
+FADE_IN=${4:-0}
+FADE_OUT=${5:-0}
+
+cargo run --release --example $1 || exit 1
+
+if [ -z "$AUDIO" ]; then
+    ffmpeg -y -r 60 -i $1/%d.png -c:v libx264 -vf "fps=60,format=yuv420p" "videos/$TSTAMP-$1.mp4"
+else
+    DURATION=$(ls $1/*.png | wc -l | awk '{ print $1 / 60 }')
+    # Skip to the offset before looping, so each loop restarts at the offset.
+    FILTERS="atrim=start=$OFFSET,asetpts=PTS-STARTPTS,aloop=loop=-1:size=2147483647"
+    if [ "$FADE_IN" != "0" ]; then
+        FILTERS="$FILTERS,afade=t=in:st=0:d=$FADE_IN"
+    fi
+    if [ "$FADE_OUT" != "0" ]; then
+        FADE_START=$(echo "$DURATION $FADE_OUT" | awk '{ print $1 - $2 }')
+        FILTERS="$FILTERS,afade=t=out:st=$FADE_START:d=$FADE_OUT"
+    fi
+    ffmpeg -y -r 60 -i $1/%d.png \
+        -i "$AUDIO" \
+        -map 0:v -map 1:a \
+        -c:v libx264 -vf "fps=60,format=yuv420p" \
+        -c:a aac -b:a 320k -af "$FILTERS" \
+        -t "$DURATION" \
+        "videos/$TSTAMP-$1.mp4"
+fi && rm -rf $1
@@ -1,6 +1,9 @@
-use crate::{App, BaseModel, Key};
+use crate::{layers::Layer, App, BaseModel, Key};
+use serde::{Deserialize, Serialize};
 
 /// The options that can be set when creating an artwork.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Options {
     /// Chromatic aberration of the animation.
     pub chroma: f32,
@@ -11,6 +14,9 @@ pub struct Options {
     pub shutter_angle: f64,
     /// Provide an extra
     pub extra_tex: Option<Vec<String>>,
+    /// Image sequences or videos in `assets/`, shown in textures that change
+    /// with time. See [`footage`](crate::footage).
+    pub footage: Option<Vec<String>>,
     pub noise_amount: f32,
 }
 
@@ -21,6 +27,7 @@ impl Default for Options {
             sample_per_frame: 1,
             shutter_angle: 0.,
             extra_tex: None,
+            footage: None,
             noise_amount: 0.,
         }
     }
@@ -32,11 +39,14 @@ impl Default for Options {
 /// template.rs in the examples/ folder of this crate.
 pub trait Artwork {
     /// This function creates a new instance of the artwork. It should define
-    /// all the attributes that the artwork will use.
+    /// all the attributes that the artwork will use, and register the
+    /// parameters it exposes in `base.params`.
     fn new(base: BaseModel) -> Self;
     /// This is the main drawing function in the artwork. It should be
     /// deterministic as a function of `time` to ensure good results if the
-    /// number of sample per frame is above 1.
+    /// number of sample per frame is above 1. It is called once per motion
+    /// blur sample and per pre-roll frame, so state advanced here, like a
+    /// physics step, advances at each call.
     fn draw_at_time(&mut self, time: f64);
     fn get_model(&self) -> &BaseModel;
     fn get_mut_model(&mut self) -> &mut BaseModel;
@@ -61,6 +71,7 @@ pub trait Artwork {
     ///         sample_per_frame: 1,
     ///         shutter_angle: 0.1,
     ///         extra_tex: None,
+    ///         footage: None,
     ///         noise_amount: 0.2
     ///     })
     /// }
@@ -68,8 +79,51 @@ pub trait Artwork {
     fn get_options() -> Option<Options> {
         None
     }
+    /// You should implement this function to draw the previous frame back in
+    /// the next one, through `base.feedback`. It returns the number of frames
+    /// drawn before the first recorded frame, so that recordings start from
+    /// the same state. See [`feedback`](crate::feedback).
+    ///
+    /// ```ignore
+    /// fn feedback_preroll() -> Option<u32> {
+    ///     Some(120)
+    /// }
+    /// ```
+    fn feedback_preroll() -> Option<u32> {
+        None
+    }
+    /// You should implement this function to draw in layers composited with
+    /// blend modes, from the bottom to the top, over `base.draw`. Each layer
+    /// takes about 150 MB of GPU memory in 4K, and each mask three times as
+    /// much. See [`layers`](crate::layers).
+    ///
+    /// ```ignore
+    /// fn layers() -> Vec<Layer> {
+    ///     vec![Layer::new("glow").blend(Blend::Screen)]
+    /// }
+    /// ```
+    fn layers() -> Vec<Layer> {
+        vec![]
+    }
+    /// You should implement this function if `new` builds some random state,
+    /// to build it again from `base.rng()`. It is called whenever `base.seed`
+    /// changes, from the keys, the panel, OSC or a preset, and before each
+    /// frame of a contact sheet, golden-image test or loop check, so that a
+    /// seed always renders the same frames.
+    ///
+    /// ```ignore
+    /// fn reseed(&mut self) {
+    ///     let mut rng = self.base.rng();
+    ///     self.offsets = (0..10).map(|_| rng.gen()).collect();
+    /// }
+    /// ```
+    fn reseed(&mut self) {}
     /// Define what happens when you press a key, useful when you want to reset
     /// some state when starting to record, or cycle through color palette when
     /// pressing P for example.
+    ///
+    /// The keys of the preview window listed in the README are forwarded too,
+    /// e.g. to regenerate some state along with the seed on S. All the letters
+    /// other than S and R are free for the artwork.
     fn key_pressed(&mut self, _app: &App, _key: Key) {}
 }
@@ -0,0 +1,316 @@
+//! Images, fonts, audio and data files of an artwork, loaded by name from
+//! `assets/`.
+//!
+//! Loading an asset returns a typed [`Handle`], which the artwork keeps to get
+//! the asset back while drawing. Loading the same name twice returns the same
+//! handle. Missing or invalid files give an [`AssetError`] naming the file.
+//!
+//! While previewing, files changed on disk are reloaded, and the
+//! [`version`](Assets::version) of their handle increases. When a changed file
+//! can't be loaded, the error is reported and the previous version is kept.
+//!
+//! ```ignore
+//! fn new(mut base: BaseModel) -> Model {
+//!     let road = base.assets.load_image("road.jpg").unwrap();
+//!     Model { base, road }
+//! }
+//!
+//! fn draw_at_time(&mut self, time: f64) {
+//!     let texture = self.base.assets.texture(self.road);
+//!     // ...
+//! }
+//! ```
+use crate::audio::AudioClip;
+use nannou::{image, text, wgpu};
+use serde::de::DeserializeOwned;
+use std::{
+    error::Error,
+    fmt, fs,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How often the files are checked for changes.
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// An image, drawn as a texture.
+pub enum Image {}
+/// A TrueType or OpenType font.
+pub enum Font {}
+/// An audio file.
+pub enum Audio {}
+/// A JSON or TOML file, or any other text file.
+pub enum Data {}
+
+/// A loaded asset of type `T`.
+pub struct Handle<T> {
+    index: usize,
+    kind: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Handle({})", self.index)
+    }
+}
+
+#[derive(Debug)]
+pub enum AssetError {
+    /// The file does not exist.
+    Missing(PathBuf),
+    /// The file can't be read as an asset of the expected type.
+    Invalid { path: PathBuf, reason: String },
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetError::Missing(path) => write!(f, "Asset {} not found", path.display()),
+            AssetError::Invalid { path, reason } => {
+                write!(f, "Asset {} is invalid: {}", path.display(), reason)
+            }
+        }
+    }
+}
+
+impl Error for AssetError {}
+
+fn invalid(path: &Path, reason: impl ToString) -> AssetError {
+    AssetError::Invalid {
+        path: path.to_path_buf(),
+        reason: reason.to_string(),
+    }
+}
+
+/// Read a data file, parsed according to its extension.
+fn load_data(path: &Path) -> Result<serde_json::Value, AssetError> {
+    let text = fs::read_to_string(path).map_err(|e| invalid(path, e))?;
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => serde_json::from_str(&text).map_err(|e| invalid(path, e)),
+        Some("toml") => toml::from_str(&text).map_err(|e| invalid(path, e)),
+        _ => Ok(serde_json::Value::String(text)),
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Image,
+    Font,
+    Audio,
+    Data,
+}
+
+enum Content {
+    Image(wgpu::Texture),
+    Font(text::Font),
+    Audio(AudioClip),
+    Data(serde_json::Value),
+}
+
+struct Entry {
+    name: String,
+    kind: Kind,
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    version: u32,
+    content: Content,
+}
+
+/// The assets loaded by an artwork.
+pub struct Assets {
+    directory: PathBuf,
+    device: Arc<wgpu::DeviceQueuePair>,
+    entries: Vec<Entry>,
+    last_check: Instant,
+}
+
+impl Assets {
+    pub(crate) fn new(directory: PathBuf, device: Arc<wgpu::DeviceQueuePair>) -> Assets {
+        Assets {
+            directory,
+            device,
+            entries: vec![],
+            last_check: Instant::now(),
+        }
+    }
+
+    fn read(&self, kind: Kind, path: &Path) -> Result<Content, AssetError> {
+        if !path.is_file() {
+            return Err(AssetError::Missing(path.to_path_buf()));
+        }
+        Ok(match kind {
+            Kind::Image => {
+                let image = image::open(path).map_err(|e| invalid(path, e))?.to_rgba8();
+                Content::Image(wgpu::Texture::load_from_image_buffer(
+                    self.device.device(),
+                    self.device.queue(),
+                    wgpu::TextureUsages::TEXTURE_BINDING,
+                    &image,
+                ))
+            }
+            Kind::Font => Content::Font(text::font::from_file(path).map_err(|e| invalid(path, e))?),
+            Kind::Audio => Content::Audio(AudioClip::load(path).map_err(|e| invalid(path, e))?),
+            Kind::Data => Content::Data(load_data(path)?),
+        })
+    }
+
+    fn load<T>(&mut self, kind: Kind, name: &str) -> Result<Handle<T>, AssetError> {
+        let handle = |index| Handle {
+            index,
+            kind: PhantomData,
+        };
+        if let Some(index) = self
+            .entries
+            .iter()
+            .position(|e| e.name == name && e.kind == kind)
+        {
+            return Ok(handle(index));
+        }
+        let path = self.directory.join(name);
+        let content = self.read(kind, &path)?;
+        self.entries.push(Entry {
+            name: name.to_string(),
+            kind,
+            modified: modified(&path),
+            path,
+            version: 0,
+            content,
+        });
+        Ok(handle(self.entries.len() - 1))
+    }
+
+    pub fn load_image(&mut self, name: &str) -> Result<Handle<Image>, AssetError> {
+        self.load(Kind::Image, name)
+    }
+
+    pub fn load_font(&mut self, name: &str) -> Result<Handle<Font>, AssetError> {
+        self.load(Kind::Font, name)
+    }
+
+    pub fn load_audio(&mut self, name: &str) -> Result<Handle<Audio>, AssetError> {
+        self.load(Kind::Audio, name)
+    }
+
+    pub fn load_data(&mut self, name: &str) -> Result<Handle<Data>, AssetError> {
+        self.load(Kind::Data, name)
+    }
+
+    pub fn texture(&self, handle: Handle<Image>) -> &wgpu::Texture {
+        match &self.entries[handle.index].content {
+            Content::Image(texture) => texture,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn font(&self, handle: Handle<Font>) -> &text::Font {
+        match &self.entries[handle.index].content {
+            Content::Font(font) => font,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn audio(&self, handle: Handle<Audio>) -> &AudioClip {
+        match &self.entries[handle.index].content {
+            Content::Audio(clip) => clip,
+            _ => unreachable!(),
+        }
+    }
+
+    /// The content of a data file. JSON and TOML files are parsed, other files
+    /// are a string.
+    pub fn data(&self, handle: Handle<Data>) -> &serde_json::Value {
+        match &self.entries[handle.index].content {
+            Content::Data(value) => value,
+            _ => unreachable!(),
+        }
+    }
+
+    /// The content of a data file, deserialized to `T`.
+    pub fn data_as<T: DeserializeOwned>(&self, handle: Handle<Data>) -> Result<T, AssetError> {
+        serde_json::from_value(self.data(handle).clone())
+            .map_err(|e| invalid(&self.entries[handle.index].path, e))
+    }
+
+    /// The number of times the asset was reloaded, to update the state
+    /// derived from it.
+    pub fn version<T>(&self, handle: Handle<T>) -> u32 {
+        self.entries[handle.index].version
+    }
+
+    /// Reload the assets whose file changed since they were loaded.
+    pub(crate) fn reload_changed(&mut self) {
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return;
+        }
+        self.last_check = Instant::now();
+        for index in 0..self.entries.len() {
+            let entry = &self.entries[index];
+            let modified = modified(&entry.path);
+            if modified.is_none() || modified == entry.modified {
+                continue;
+            }
+            let content = self.read(entry.kind, &entry.path);
+            let entry = &mut self.entries[index];
+            // A file that fails to load is only reported once per change.
+            entry.modified = modified;
+            match content {
+                Ok(content) => {
+                    entry.content = content;
+                    entry.version += 1;
+                    println!("Reloaded {}", entry.name);
+                }
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_data, AssetError};
+    use std::{fs, path::Path};
+
+    #[test]
+    fn parse_data_files() {
+        let directory =
+            std::env::temp_dir().join(format!("artworks-assets-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let json = directory.join("a.json");
+        fs::write(&json, r#"{"speed": 0.5}"#).unwrap();
+        assert_eq!(load_data(&json).unwrap()["speed"], 0.5);
+        let toml = directory.join("a.toml");
+        fs::write(&toml, "speed = 0.5\nname = \"a\"").unwrap();
+        assert_eq!(load_data(&toml).unwrap()["name"], "a");
+        let text = directory.join("a.txt");
+        fs::write(&text, "hello").unwrap();
+        assert_eq!(load_data(&text).unwrap(), "hello");
+
+        fs::write(&json, "{").unwrap();
+        let error = load_data(&json).unwrap_err().to_string();
+        assert!(error.starts_with(&format!("Asset {} is invalid", json.display())));
+        fs::remove_dir_all(&directory).unwrap();
+
+        let missing = AssetError::Missing(Path::new("assets/road.jpg").to_path_buf());
+        assert_eq!(missing.to_string(), "Asset assets/road.jpg not found");
+    }
+}
@@ -0,0 +1,290 @@
+//! Onset and beat detection, to synchronize events of an animation with the
+//! hits of a music track.
+//!
+//! All the times returned by a [`BeatTrack`] are expressed in the time of the
+//! artwork, between 0 and 1, so they can be compared directly with the `time`
+//! given to [`Artwork::draw_at_time`](crate::Artwork::draw_at_time).
+//!
+//! ```ignore
+//! let beats = BeatTrack::load(Path::new("assets/track.wav"), FPS, FPS * 15).unwrap();
+//! // A flash that fades out after each beat.
+//! let flash = 1. - beats.beat_phase(time);
+//! ```
+use super::{hann_window, AudioClip};
+use rustfft::{num_complex::Complex, FftPlanner};
+use std::{io, path::Path};
+
+/// Size and hop of the analysis windows, in samples.
+const WINDOW_SIZE: usize = 1024;
+const HOP_SIZE: usize = 512;
+/// The range of tempi considered, in beats per minute.
+const MIN_TEMPO: f64 = 60.;
+const MAX_TEMPO: f64 = 200.;
+/// Tempi are biased towards this value to choose between multiples of the
+/// same pulse.
+const PREFERRED_TEMPO: f64 = 120.;
+/// Minimum time between two onsets, in seconds.
+const MIN_ONSET_GAP: f64 = 0.05;
+
+/// The rhythmic events of an audio clip.
+#[derive(Clone, Debug)]
+pub struct BeatTrack {
+    /// Estimated tempo, in beats per minute.
+    pub tempo: f64,
+    /// Times of the detected onsets, i.e. notes and hits.
+    pub onsets: Vec<f64>,
+    /// Times of the beats, regularly spaced at the tempo.
+    pub beats: Vec<f64>,
+}
+
+impl BeatTrack {
+    /// Load and analyse an audio file for an animation of `n_frames` frames at
+    /// `fps` frames per second.
+    pub fn load(path: &Path, fps: u32, n_frames: u32) -> io::Result<BeatTrack> {
+        let clip = AudioClip::load(path)?;
+        Ok(BeatTrack::analyze(&clip, n_frames as f64 / fps as f64))
+    }
+
+    /// Detect the onsets and beats of `clip`, for an animation lasting
+    /// `duration` seconds. Events after the end of the animation are dropped.
+    pub fn analyze(clip: &AudioClip, duration: f64) -> BeatTrack {
+        let envelope = onset_envelope(clip);
+        let frame_duration = HOP_SIZE as f64 / clip.sample_rate as f64;
+        // The time at the center of the window of an envelope frame.
+        let to_time = |frame: f64| {
+            (frame * HOP_SIZE as f64 + WINDOW_SIZE as f64 / 2.) / clip.sample_rate as f64 / duration
+        };
+
+        let min_gap = (MIN_ONSET_GAP / frame_duration).ceil() as usize;
+        let onsets = pick_peaks(&envelope, min_gap)
+            .into_iter()
+            .map(|frame| to_time(frame as f64))
+            .filter(|time| *time < 1.)
+            .collect();
+
+        let (tempo, beats) = match estimate_period(&envelope, frame_duration) {
+            Some(period) => (
+                60. / (period * frame_duration),
+                beat_grid(&envelope, period)
+                    .into_iter()
+                    .map(to_time)
+                    .filter(|time| *time < 1.)
+                    .collect(),
+            ),
+            None => (0., vec![]),
+        };
+        BeatTrack {
+            tempo,
+            onsets,
+            beats,
+        }
+    }
+
+    /// The time elapsed since the last beat, or `None` before the first one.
+    pub fn time_since_last_beat(&self, time: f64) -> Option<f64> {
+        last_before(&self.beats, time).map(|beat| time - beat)
+    }
+
+    /// The time elapsed since the last onset, or `None` before the first one.
+    pub fn time_since_last_onset(&self, time: f64) -> Option<f64> {
+        last_before(&self.onsets, time).map(|onset| time - onset)
+    }
+
+    /// The number of beats that happened up to `time`.
+    pub fn beat_index(&self, time: f64) -> usize {
+        self.beats.partition_point(|beat| *beat <= time)
+    }
+
+    /// The position of `time` between the previous beat (0) and the next one
+    /// (1). The beat grid is extended at the tempo before the first and after
+    /// the last detected beat.
+    pub fn beat_phase(&self, time: f64) -> f64 {
+        let (first, period) = match (self.beats.first(), self.beat_period()) {
+            (Some(first), Some(period)) => (*first, period),
+            _ => return 0.,
+        };
+        let index = self.beat_index(time);
+        if index == 0 || index == self.beats.len() {
+            return ((time - first) / period).rem_euclid(1.);
+        }
+        let (previous, next) = (self.beats[index - 1], self.beats[index]);
+        (time - previous) / (next - previous)
+    }
+
+    /// The duration of a beat, in the time of the artwork.
+    pub fn beat_period(&self) -> Option<f64> {
+        if self.beats.len() < 2 {
+            return None;
+        }
+        Some((self.beats[self.beats.len() - 1] - self.beats[0]) / (self.beats.len() - 1) as f64)
+    }
+}
+
+fn last_before(times: &[f64], time: f64) -> Option<f64> {
+    let index = times.partition_point(|t| *t <= time);
+    index.checked_sub(1).map(|i| times[i])
+}
+
+/// The spectral flux of the clip: how much the magnitude of the spectrum
+/// increases from one window to the next. It peaks at the onsets.
+fn onset_envelope(clip: &AudioClip) -> Vec<f32> {
+    let fft = FftPlanner::new().plan_fft_forward(WINDOW_SIZE);
+    let hann = hann_window(WINDOW_SIZE);
+    let mut previous = vec![0.; WINDOW_SIZE / 2];
+    let n_windows = clip.samples.len().saturating_sub(WINDOW_SIZE) / HOP_SIZE + 1;
+    (0..n_windows)
+        .map(|w| {
+            let start = w * HOP_SIZE;
+            let mut spectrum: Vec<Complex<f32>> = (0..WINDOW_SIZE)
+                .map(|i| {
+                    let sample = clip.samples.get(start + i).copied().unwrap_or(0.);
+                    Complex::new(sample * hann[i], 0.)
+                })
+                .collect();
+            fft.process(&mut spectrum);
+            let mut flux = 0.;
+            for (magnitude, previous) in spectrum.iter().map(|c| c.norm()).zip(previous.iter_mut())
+            {
+                // Log compression makes quiet onsets visible next to loud ones.
+                let magnitude = (1. + magnitude).ln();
+                flux += (magnitude - *previous).max(0.);
+                *previous = magnitude;
+            }
+            flux
+        })
+        .collect()
+}
+
+/// The local maxima of the envelope that stand above its moving average, at
+/// least `min_gap` frames apart.
+fn pick_peaks(envelope: &[f32], min_gap: usize) -> Vec<usize> {
+    let max = envelope.iter().copied().fold(0., f32::max);
+    if max <= 0. {
+        return vec![];
+    }
+    let mut peaks: Vec<usize> = vec![];
+    for i in 0..envelope.len() {
+        let neighbours = i.saturating_sub(3)..(i + 4).min(envelope.len());
+        if envelope[neighbours].iter().any(|v| *v > envelope[i]) {
+            continue;
+        }
+        let around = i.saturating_sub(16)..(i + 17).min(envelope.len());
+        let mean = envelope[around.clone()].iter().sum::<f32>() / around.len() as f32;
+        if envelope[i] < mean + 0.1 * max {
+            continue;
+        }
+        if peaks.last().map_or(true, |last| i - last >= min_gap) {
+            peaks.push(i);
+        }
+    }
+    peaks
+}
+
+/// The beat period, in envelope frames, found from the autocorrelation of the
+/// envelope.
+fn estimate_period(envelope: &[f32], frame_duration: f64) -> Option<f64> {
+    let mean = envelope.iter().sum::<f32>() / envelope.len().max(1) as f32;
+    let centered: Vec<f64> = envelope.iter().map(|v| (v - mean) as f64).collect();
+    let min_lag = (60. / MAX_TEMPO / frame_duration).floor() as usize;
+    let max_lag = ((60. / MIN_TEMPO / frame_duration).ceil() as usize).min(centered.len() / 2);
+    let score = |lag: usize| {
+        let correlation: f64 = (lag..centered.len())
+            .map(|i| centered[i] * centered[i - lag])
+            .sum::<f64>()
+            / (centered.len() - lag) as f64;
+        let tempo = 60. / (lag as f64 * frame_duration);
+        correlation * (-0.5 * (tempo / PREFERRED_TEMPO).log2().powi(2)).exp()
+    };
+    let best = (min_lag.max(1)..=max_lag)
+        .map(|lag| (lag, score(lag)))
+        .filter(|(_, score)| *score > 0.)
+        .max_by(|a, b| a.1.total_cmp(&b.1))?
+        .0;
+    // Refine the period with a parabola through the scores around the best lag.
+    let (before, at, after) = (score(best - 1), score(best), score(best + 1));
+    let denominator = before - 2. * at + after;
+    let offset = if denominator < 0. {
+        (0.5 * (before - after) / denominator).clamp(-0.5, 0.5)
+    } else {
+        0.
+    };
+    Some(best as f64 + offset)
+}
+
+/// Frames of a regular grid with the given period, shifted to line up with the
+/// strongest onsets.
+fn beat_grid(envelope: &[f32], period: f64) -> Vec<f64> {
+    let grid = |phase: f64| {
+        (0..)
+            .map(move |k| phase + k as f64 * period)
+            .take_while(|frame| frame.round() < envelope.len() as f64)
+    };
+    let phase = (0..period.ceil() as usize)
+        .map(|phase| phase as f64)
+        .max_by(|a, b| {
+            let score =
+                |phase: f64| -> f32 { grid(phase).map(|f| envelope[f.round() as usize]).sum() };
+            score(*a).total_cmp(&score(*b))
+        })
+        .unwrap_or(0.);
+    grid(phase).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BeatTrack;
+    use crate::audio::AudioClip;
+
+    /// A click every half second, i.e. at 120 beats per minute, starting at
+    /// 0.25 seconds.
+    fn click_track(seconds: f64) -> AudioClip {
+        let sample_rate = 44100;
+        let samples = (0..(seconds * sample_rate as f64) as usize)
+            .map(|i| {
+                let t = i as f64 / sample_rate as f64;
+                let since_click = (t - 0.25).rem_euclid(0.5);
+                if t >= 0.25 && since_click < 0.02 {
+                    ((since_click * 2000. * std::f64::consts::TAU).sin()
+                        * (1. - since_click / 0.02)) as f32
+                } else {
+                    0.
+                }
+            })
+            .collect();
+        AudioClip {
+            samples,
+            sample_rate,
+        }
+    }
+
+    #[test]
+    fn detect_clicks() {
+        // The animation lasts 10 seconds, longer than the audio.
+        let beats = BeatTrack::analyze(&click_track(8.), 10.);
+        assert!((beats.tempo - 120.).abs() < 3., "tempo {}", beats.tempo);
+        assert_eq!(beats.onsets.len(), 16);
+        for (i, onset) in beats.onsets.iter().enumerate() {
+            let expected = (0.25 + 0.5 * i as f64) / 10.;
+            assert!((onset - expected).abs() < 0.003, "onset {} at {}", i, onset);
+        }
+        let period = beats.beat_period().unwrap();
+        assert!((period - 0.05).abs() < 0.002);
+        assert!((beats.beats[0] - 0.025).abs() < 0.003);
+    }
+
+    #[test]
+    fn beat_helpers() {
+        let beats = BeatTrack {
+            tempo: 120.,
+            onsets: vec![0.1, 0.35],
+            beats: vec![0.1, 0.3, 0.5],
+        };
+        assert_eq!(beats.time_since_last_beat(0.05), None);
+        assert!((beats.time_since_last_beat(0.35).unwrap() - 0.05).abs() < 1e-9);
+        assert!((beats.time_since_last_onset(0.4).unwrap() - 0.05).abs() < 1e-9);
+        assert_eq!(beats.beat_index(0.3), 2);
+        assert!((beats.beat_phase(0.4) - 0.5).abs() < 1e-9);
+        assert!((beats.beat_phase(0.6) - 0.5).abs() < 1e-9);
+        assert!((beats.beat_phase(0.) - 0.5).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,246 @@
+//! Analysis of live audio input.
+//!
+//! The audio callback pushes the samples it receives into a lock-free ring
+//! buffer, and the render thread computes the features of the latest window of
+//! samples at each update. The same ring buffer can be fed from an audio file
+//! instead of a sound card, which makes live artworks testable.
+//!
+//! The input is enabled from the command line, and its features are available
+//! in `self.base.audio_input` when drawing:
+//!
+//! ``` sh
+//! # Analyse the default input device.
+//! cargo run --release --example flowfield -- --audio-input
+//! # Stream an audio file in real time instead.
+//! cargo run --release --example flowfield -- --audio-input-file assets/track.wav
+//! ```
+use super::{AudioClip, AudioFrame, FrameAnalyzer, N_BANDS, WINDOW_SIZE};
+use nannou_audio as audio;
+use ringbuf::{Consumer, Producer, RingBuffer};
+use std::{error::Error, io, path::Path};
+
+/// The sample rate requested from the input device.
+const SAMPLE_RATE: u32 = 44100;
+/// The ring buffer holds this many windows of samples, so that the audio
+/// thread never waits for a slow frame.
+const BUFFER_WINDOWS: usize = 8;
+/// How much the peaks used for normalization decay at each update.
+const PEAK_DECAY: f32 = 0.995;
+
+/// Create the ring buffer shared by an audio source and a [`LiveAnalyzer`].
+pub fn ring_buffer() -> (Producer<f32>, Consumer<f32>) {
+    RingBuffer::new(BUFFER_WINDOWS * WINDOW_SIZE).split()
+}
+
+/// The state given to the capture callback of the input stream.
+struct Capture {
+    producer: Producer<f32>,
+}
+
+fn capture(capture: &mut Capture, buffer: &audio::Buffer) {
+    let channels = buffer.channels() as f32;
+    for frame in buffer.frames() {
+        // Samples are dropped when the render thread is late.
+        capture
+            .producer
+            .push(frame.iter().sum::<f32>() / channels)
+            .ok();
+    }
+}
+
+/// Pushes the samples of an audio clip into the ring buffer, standing in for
+/// a sound card.
+pub struct ClipFeeder {
+    producer: Producer<f32>,
+    clip: AudioClip,
+    position: f64,
+}
+
+impl ClipFeeder {
+    pub fn new(producer: Producer<f32>, clip: AudioClip) -> ClipFeeder {
+        ClipFeeder {
+            producer,
+            clip,
+            position: 0.,
+        }
+    }
+
+    /// Push the next `seconds` of audio, looping at the end of the clip.
+    pub fn feed(&mut self, seconds: f64) {
+        if self.clip.samples.is_empty() {
+            return;
+        }
+        let start = self.position as usize;
+        self.position += seconds * self.clip.sample_rate as f64;
+        let n_samples = self.clip.samples.len();
+        for i in start..self.position as usize {
+            self.producer.push(self.clip.samples[i % n_samples]).ok();
+        }
+        self.position %= n_samples as f64;
+    }
+}
+
+enum Source {
+    Device {
+        // The stream is kept alive as long as the analyzer.
+        _stream: audio::Stream<Capture>,
+    },
+    File(ClipFeeder),
+    /// Samples are pushed into the ring buffer by the owner of the producer.
+    External,
+}
+
+/// Computes the features of the latest window of samples of a live input.
+pub struct LiveAnalyzer {
+    source: Source,
+    consumer: Consumer<f32>,
+    window: Vec<f32>,
+    analyzer: FrameAnalyzer,
+    features: AudioFrame,
+    peaks: AudioFrame,
+}
+
+impl LiveAnalyzer {
+    /// Analyse the samples pushed to the producer of `consumer`, recorded at
+    /// `sample_rate`.
+    pub fn new(consumer: Consumer<f32>, sample_rate: u32) -> LiveAnalyzer {
+        LiveAnalyzer::with_source(Source::External, consumer, sample_rate)
+    }
+
+    fn with_source(source: Source, consumer: Consumer<f32>, sample_rate: u32) -> LiveAnalyzer {
+        LiveAnalyzer {
+            source,
+            consumer,
+            window: vec![0.; WINDOW_SIZE],
+            analyzer: FrameAnalyzer::new(sample_rate),
+            features: AudioFrame::default(),
+            peaks: AudioFrame::default(),
+        }
+    }
+
+    /// Analyse the default input device of the system.
+    pub fn from_default_device() -> Result<LiveAnalyzer, Box<dyn Error>> {
+        let (producer, consumer) = ring_buffer();
+        let host = audio::Host::new();
+        let stream = host
+            .new_input_stream(Capture { producer })
+            .capture(capture)
+            .sample_rate(SAMPLE_RATE)
+            .build()?;
+        stream.play()?;
+        Ok(LiveAnalyzer::with_source(
+            Source::Device { _stream: stream },
+            consumer,
+            SAMPLE_RATE,
+        ))
+    }
+
+    /// Stream an audio file through the ring buffer in real time, as if it was
+    /// coming from a sound card.
+    pub fn from_file(path: &Path) -> io::Result<LiveAnalyzer> {
+        let clip = AudioClip::load(path)?;
+        let (producer, consumer) = ring_buffer();
+        let sample_rate = clip.sample_rate;
+        Ok(LiveAnalyzer::with_source(
+            Source::File(ClipFeeder::new(producer, clip)),
+            consumer,
+            sample_rate,
+        ))
+    }
+
+    /// Open the input given with `--audio-input` or
+    /// `--audio-input-file PATH` on the command line, if any.
+    pub fn from_args() -> Option<LiveAnalyzer> {
+        let args: Vec<String> = std::env::args().collect();
+        let analyzer = if args.iter().any(|a| a == "--audio-input") {
+            LiveAnalyzer::from_default_device()
+        } else if let Some(i) = args.iter().position(|a| a == "--audio-input-file") {
+            let path = args.get(i + 1)?;
+            LiveAnalyzer::from_file(Path::new(path)).map_err(|e| e.into())
+        } else {
+            return None;
+        };
+        analyzer
+            .map_err(|e| eprintln!("Could not open audio input: {}", e))
+            .ok()
+    }
+
+    /// Read the samples received since the last update, `dt` seconds ago, and
+    /// compute the features of the latest window.
+    pub fn update(&mut self, dt: f64) -> AudioFrame {
+        if let Source::File(feeder) = &mut self.source {
+            feeder.feed(dt);
+        }
+        let received = self.consumer.len();
+        if received > 0 {
+            // Only the last window of samples matters.
+            self.consumer.discard(received.saturating_sub(WINDOW_SIZE));
+            let n = received.min(WINDOW_SIZE);
+            self.window.rotate_left(n);
+            self.consumer.pop_slice(&mut self.window[WINDOW_SIZE - n..]);
+        }
+        self.features = self.analyzer.analyze(&self.window);
+
+        self.peaks.rms = (self.peaks.rms * PEAK_DECAY).max(self.features.rms);
+        for i in 0..N_BANDS {
+            self.peaks.bands[i] = (self.peaks.bands[i] * PEAK_DECAY).max(self.features.bands[i]);
+        }
+        self.features
+    }
+
+    /// The features computed at the last update.
+    pub fn features(&self) -> AudioFrame {
+        self.features
+    }
+
+    /// The features at the last update, with the RMS and bands divided by
+    /// their recent peak so that they stay between 0 and 1 whatever the input
+    /// level.
+    pub fn normalized_features(&self) -> AudioFrame {
+        let normalize = |value: f32, peak: f32| if peak > 0. { value / peak } else { 0. };
+        let mut features = self.features;
+        features.rms = normalize(features.rms, self.peaks.rms);
+        for i in 0..N_BANDS {
+            features.bands[i] = normalize(features.bands[i], self.peaks.bands[i]);
+        }
+        features
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ring_buffer, ClipFeeder, LiveAnalyzer};
+    use crate::audio::{AudioClip, AudioFeatures};
+    use std::f32::consts::TAU;
+
+    #[test]
+    fn same_features_as_offline_analysis() {
+        let sample_rate = 44100;
+        let clip = AudioClip {
+            samples: (0..sample_rate)
+                .map(|i| {
+                    let t = i as f32 / sample_rate as f32;
+                    t * (TAU * 1000. * t).sin()
+                })
+                .collect(),
+            sample_rate,
+        };
+        let (producer, consumer) = ring_buffer();
+        let mut feeder = ClipFeeder::new(producer, clip.clone());
+        let mut live = LiveAnalyzer::new(consumer, sample_rate);
+        let offline = AudioFeatures::analyze(&clip, 60, 60);
+
+        // Feed half a second of audio, frame by frame.
+        for _ in 0..30 {
+            feeder.feed(1. / 60.);
+            live.update(1. / 60.);
+        }
+        // The live window ends at 0.5 s while the offline one is centered on
+        // the frame, so compare with the frame a bit earlier.
+        let expected = offline.at_time(0.5 - 1024. / 44100.);
+        let features = live.features();
+        assert!((features.rms - expected.rms).abs() < 0.01);
+        assert!((features.centroid - expected.centroid).abs() < 20.);
+        assert_eq!(live.normalized_features().rms, 1.);
+    }
+}
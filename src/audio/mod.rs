@@ -0,0 +1,287 @@
+//! Audio analysis for audio-reactive artworks.
+//!
+//! An audio file is loaded once and analysed offline into one set of features
+//! per frame of the animation. Querying the features at a given `time` is then
+//! deterministic, and the sub-frames of the motion blur get values
+//! interpolated between two frames.
+//!
+//! ```ignore
+//! fn new(base: BaseModel) -> Model {
+//!     let path = Path::new("assets/track.wav");
+//!     let audio = AudioFeatures::load(path, FPS, FPS * 15).unwrap();
+//!     Model { base, audio }
+//! }
+//!
+//! fn draw_at_time(&mut self, time: f64) {
+//!     let features = self.audio.at_time(time);
+//!     let radius = 100. + 400. * features.rms;
+//!     // ...
+//! }
+//! ```
+pub mod beats;
+pub mod live;
+
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+use std::{f32::consts::PI, io, path::Path, sync::Arc};
+
+/// Number of samples used to compute the spectrum of a frame.
+pub(crate) const WINDOW_SIZE: usize = 2048;
+/// Number of frequency bands in [`AudioFrame::bands`].
+pub const N_BANDS: usize = 8;
+/// The frequency range covered by the bands, in Hz.
+const MIN_FREQUENCY: f32 = 40.;
+const MAX_FREQUENCY: f32 = 16000.;
+
+/// The Hann window, used to taper the audio before computing a spectrum.
+fn hann_window(size: usize) -> Vec<f32> {
+    (0..size)
+        .map(|i| 0.5 - 0.5 * (2. * PI * i as f32 / size as f32).cos())
+        .collect()
+}
+
+/// A mono audio clip.
+#[derive(Clone, Debug)]
+pub struct AudioClip {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl AudioClip {
+    /// Load a WAV, FLAC or OGG Vorbis file, mixing all its channels down to
+    /// mono.
+    pub fn load(path: &Path) -> io::Result<AudioClip> {
+        let mut reader =
+            audrey::open(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let description = reader.description();
+        let channels = description.channel_count() as usize;
+        let interleaved = reader
+            .samples::<f32>()
+            .collect::<Result<Vec<f32>, _>>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let samples = interleaved
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+            .collect();
+        Ok(AudioClip {
+            samples,
+            sample_rate: description.sample_rate(),
+        })
+    }
+
+    /// The duration of the clip, in seconds.
+    pub fn duration(&self) -> f64 {
+        self.samples.len() as f64 / self.sample_rate as f64
+    }
+}
+
+/// The features of the audio for one frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AudioFrame {
+    /// Root mean square amplitude of the signal.
+    pub rms: f32,
+    /// Energy in logarithmically spaced frequency bands, from bass to treble.
+    pub bands: [f32; N_BANDS],
+    /// Spectral centroid, in Hz. Higher values sound brighter.
+    pub centroid: f32,
+}
+
+impl AudioFrame {
+    /// Linear interpolation between two frames.
+    pub fn lerp(&self, other: &AudioFrame, t: f32) -> AudioFrame {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        let mut bands = [0.; N_BANDS];
+        for (i, band) in bands.iter_mut().enumerate() {
+            *band = mix(self.bands[i], other.bands[i]);
+        }
+        AudioFrame {
+            rms: mix(self.rms, other.rms),
+            bands,
+            centroid: mix(self.centroid, other.centroid),
+        }
+    }
+}
+
+/// Computes the features of windows of [`WINDOW_SIZE`] samples.
+pub(crate) struct FrameAnalyzer {
+    fft: Arc<dyn Fft<f32>>,
+    hann: Vec<f32>,
+    band_edges: Vec<f32>,
+    bin_frequency: f32,
+}
+
+impl FrameAnalyzer {
+    pub fn new(sample_rate: u32) -> FrameAnalyzer {
+        FrameAnalyzer {
+            fft: FftPlanner::new().plan_fft_forward(WINDOW_SIZE),
+            hann: hann_window(WINDOW_SIZE),
+            band_edges: (0..=N_BANDS)
+                .map(|i| {
+                    MIN_FREQUENCY * (MAX_FREQUENCY / MIN_FREQUENCY).powf(i as f32 / N_BANDS as f32)
+                })
+                .collect(),
+            bin_frequency: sample_rate as f32 / WINDOW_SIZE as f32,
+        }
+    }
+
+    pub fn analyze(&self, window: &[f32]) -> AudioFrame {
+        let rms = (window.iter().map(|s| s * s).sum::<f32>() / WINDOW_SIZE as f32).sqrt();
+
+        let mut spectrum: Vec<Complex<f32>> = window
+            .iter()
+            .zip(self.hann.iter())
+            .map(|(s, w)| Complex::new(s * w, 0.))
+            .collect();
+        self.fft.process(&mut spectrum);
+
+        let mut bands = [0.; N_BANDS];
+        let (mut weighted, mut total) = (0., 0.);
+        for (bin, value) in spectrum.iter().enumerate().take(WINDOW_SIZE / 2).skip(1) {
+            let frequency = bin as f32 * self.bin_frequency;
+            let energy = value.norm_sqr();
+            weighted += frequency * energy;
+            total += energy;
+            if let Some(band) = self
+                .band_edges
+                .windows(2)
+                .position(|e| frequency >= e[0] && frequency < e[1])
+            {
+                bands[band] += energy;
+            }
+        }
+        AudioFrame {
+            rms,
+            bands,
+            centroid: if total > 0. { weighted / total } else { 0. },
+        }
+    }
+}
+
+/// The features of an audio clip, precomputed for each frame of an animation.
+#[derive(Clone, Debug)]
+pub struct AudioFeatures {
+    frames: Vec<AudioFrame>,
+}
+
+impl AudioFeatures {
+    /// Load and analyse an audio file for an animation of `n_frames` frames at
+    /// `fps` frames per second. The features are normalized, see
+    /// [`AudioFeatures::normalize`].
+    pub fn load(path: &Path, fps: u32, n_frames: u32) -> io::Result<AudioFeatures> {
+        let mut features = AudioFeatures::analyze(&AudioClip::load(path)?, fps, n_frames);
+        features.normalize();
+        Ok(features)
+    }
+
+    /// Compute the features of each frame, using a window of audio centered on
+    /// the time of the frame. Frames past the end of the clip are silent.
+    pub fn analyze(clip: &AudioClip, fps: u32, n_frames: u32) -> AudioFeatures {
+        let analyzer = FrameAnalyzer::new(clip.sample_rate);
+        let frames = (0..n_frames)
+            .map(|frame| {
+                let center = (frame as f64 / fps as f64 * clip.sample_rate as f64) as isize;
+                let start = center - WINDOW_SIZE as isize / 2;
+                let window: Vec<f32> = (start..start + WINDOW_SIZE as isize)
+                    .map(|i| {
+                        usize::try_from(i)
+                            .ok()
+                            .and_then(|i| clip.samples.get(i))
+                            .copied()
+                            .unwrap_or(0.)
+                    })
+                    .collect();
+                analyzer.analyze(&window)
+            })
+            .collect();
+        AudioFeatures { frames }
+    }
+
+    /// Scale the RMS and each band so that their maximum over the clip is 1,
+    /// which makes them easy to use as animation parameters.
+    pub fn normalize(&mut self) {
+        let max_rms = self.frames.iter().map(|f| f.rms).fold(0., f32::max);
+        let mut max_bands = [0f32; N_BANDS];
+        for frame in self.frames.iter() {
+            for (max, band) in max_bands.iter_mut().zip(frame.bands.iter()) {
+                *max = max.max(*band);
+            }
+        }
+        for frame in self.frames.iter_mut() {
+            if max_rms > 0. {
+                frame.rms /= max_rms;
+            }
+            for (band, max) in frame.bands.iter_mut().zip(max_bands.iter()) {
+                if *max > 0. {
+                    *band /= max;
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// The features at a fractional frame, interpolated between the frames
+    /// around it.
+    pub fn at_frame(&self, frame: f64) -> AudioFrame {
+        if self.frames.is_empty() {
+            return AudioFrame::default();
+        }
+        let last = self.frames.len() - 1;
+        let frame = frame.clamp(0., last as f64);
+        let index = frame.floor() as usize;
+        let next = (index + 1).min(last);
+        self.frames[index].lerp(&self.frames[next], frame.fract() as f32)
+    }
+
+    /// The features at a `time` between 0 and 1, as given to
+    /// [`Artwork::draw_at_time`](crate::Artwork::draw_at_time).
+    pub fn at_time(&self, time: f64) -> AudioFrame {
+        self.at_frame(time * self.frames.len() as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AudioClip, AudioFeatures, N_BANDS};
+    use std::f32::consts::TAU;
+
+    fn sine(frequency: f32, seconds: f32) -> AudioClip {
+        let sample_rate = 44100;
+        AudioClip {
+            samples: (0..(seconds * sample_rate as f32) as usize)
+                .map(|i| (TAU * frequency * i as f32 / sample_rate as f32).sin())
+                .collect(),
+            sample_rate,
+        }
+    }
+
+    #[test]
+    fn sine_features() {
+        let features = AudioFeatures::analyze(&sine(440., 1.), 60, 60);
+        let frame = features.at_frame(30.);
+        assert!((frame.rms - 1. / 2f32.sqrt()).abs() < 0.01);
+        assert!((frame.centroid - 440.).abs() < 20.);
+        let loudest = (0..N_BANDS)
+            .max_by(|a, b| frame.bands[*a].total_cmp(&frame.bands[*b]))
+            .unwrap();
+        // 440 Hz falls in the fourth band, from 378 to 800 Hz.
+        assert_eq!(loudest, 3);
+    }
+
+    #[test]
+    fn silent_past_the_end_and_interpolated() {
+        let mut features = AudioFeatures::analyze(&sine(440., 0.5), 60, 60);
+        features.normalize();
+        assert_eq!(features.len(), 60);
+        assert_eq!(features.at_time(0.99).rms, 0.);
+        let (a, b) = (features.at_frame(29.), features.at_frame(31.));
+        let middle = features.at_frame(30.5);
+        assert!(middle.rms <= a.rms.max(b.rms) && middle.rms >= a.rms.min(b.rms));
+        assert!(features.at_frame(10.).rms <= 1.);
+    }
+}
@@ -0,0 +1,122 @@
+//! Rendering of a list of frames instead of the interactive preview.
+//!
+//! Contact sheets, golden-image tests and loop checks render given frames at given seeds
+//! through the usual render path, one frame per update. A small copy of each
+//! frame is read back from the GPU, and the copies are processed once all the
+//! frames are rendered, after which the app quits.
+use crate::{contact_sheet::ContactSheet, golden::Golden, loop_check::LoopCheck};
+use nannou::{
+    image::{imageops, RgbaImage},
+    prelude::*,
+    wgpu,
+};
+use std::sync::{Arc, Mutex};
+
+/// A frame to render, and the seed to render it with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Cell {
+    pub seed: i32,
+    pub frame: u32,
+}
+
+/// What to do with the rendered frames.
+pub(crate) enum Purpose {
+    ContactSheet(ContactSheet),
+    Golden(Golden),
+    LoopCheck(LoopCheck),
+}
+
+pub(crate) struct Batch {
+    cells: Vec<Cell>,
+    /// The width of the copies of the frames, in pixels.
+    width: u32,
+    purpose: Purpose,
+    next: usize,
+    images: Arc<Mutex<Vec<Option<RgbaImage>>>>,
+    /// The frames that could not be read from the GPU.
+    errors: Arc<Mutex<Vec<String>>>,
+}
+
+impl Batch {
+    pub fn new(cells: Vec<Cell>, width: u32, purpose: Purpose) -> Batch {
+        Batch {
+            images: Arc::new(Mutex::new(vec![None; cells.len()])),
+            cells,
+            width,
+            purpose,
+            next: 0,
+            errors: Arc::new(Mutex::new(vec![])),
+        }
+    }
+
+    /// The batch asked for on the command line, if any, for an animation of
+    /// `n_frames` frames currently rendered at `seed`.
+    pub fn from_args(n_frames: u32, seed: i32) -> Option<Batch> {
+        ContactSheet::from_args(n_frames)
+            .or_else(|| Golden::from_args(n_frames))
+            .or_else(|| LoopCheck::from_args(n_frames, seed))
+    }
+
+    /// The index of the next cell to render and the cell, if any.
+    pub fn next_cell(&mut self) -> Option<(usize, Cell)> {
+        let cell = *self.cells.get(self.next)?;
+        self.next += 1;
+        Some((self.next - 1, cell))
+    }
+
+    /// Whether all the cells were rendered. Their images may still be being
+    /// read from the GPU.
+    pub fn is_rendered(&self) -> bool {
+        self.next >= self.cells.len()
+    }
+
+    /// Read a small copy of the frame rendered for the cell `index`.
+    pub fn capture(&self, index: usize, snapshot: wgpu::TextueSnapshot) {
+        let images = self.images.clone();
+        let errors = self.errors.clone();
+        let width = self.width;
+        let cell = self.cells[index];
+        let read = snapshot.read(move |result| match result {
+            Ok(image) => {
+                let image = image.to_owned();
+                let (w, h) = image.dimensions();
+                let image = imageops::thumbnail(&image, width, width * h / w);
+                images.lock().unwrap()[index] = Some(image);
+            }
+            Err(e) => errors.lock().unwrap().push(format!(
+                "Could not read frame {} of seed {} from the GPU: {}",
+                cell.frame, cell.seed, e
+            )),
+        });
+        if let Err(e) = read {
+            self.errors.lock().unwrap().push(format!(
+                "Could not read frame {} of seed {} from the GPU: {:?}",
+                cell.frame, cell.seed, e
+            ));
+        }
+    }
+
+    /// Process the images once they are all read, and return whether the
+    /// batch succeeded.
+    pub fn finish(&self, app: &App) -> bool {
+        let errors = self.errors.lock().unwrap();
+        if !errors.is_empty() {
+            for error in errors.iter() {
+                eprintln!("{}", error);
+            }
+            return false;
+        }
+        let images = self.images.lock().unwrap();
+        let (cells, images): (Vec<Cell>, Vec<RgbaImage>) = self
+            .cells
+            .iter()
+            .zip(images.iter())
+            .filter_map(|(cell, image)| image.clone().map(|image| (*cell, image)))
+            .unzip();
+        match &self.purpose {
+            Purpose::ContactSheet(sheet) => sheet.finish(app, &cells, &images),
+            Purpose::Golden(golden) => golden.finish(app, &cells, &images),
+            Purpose::LoopCheck(check) => check.finish(&cells, &images),
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! Contact sheets of an artwork rendered with many seeds.
+//!
+//! Instead of playing the animation, the preview renders one or a few frames
+//! for each of N random seeds through the usual render path, one cell per
+//! update, and quits. The thumbnails are laid out in a grid labelled with the
+//! seed of each cell, and saved in `contact_sheets/<artwork name>/`:
+//!
+//! ``` sh
+//! # The middle frame of 16 seeds.
+//! cargo run --release --example flowfield -- --contact-sheet 16
+//! # Three frames of 8 seeds, one row per seed.
+//! cargo run --release --example flowfield -- --contact-sheet 8 --contact-frames 0,300,450
+//! ```
+//!
+//! The seed of each cell is set in `self.base.seed` before
+//! [`Artwork::reseed`](crate::Artwork::reseed) is called, so artworks that
+//! build random state in `new` should build it again there from the seed.
+use crate::{
+    batch::{Batch, Cell, Purpose},
+    contact_sheet_directory,
+};
+use nannou::{
+    image::{imageops, Rgba, RgbaImage},
+    prelude::*,
+};
+
+/// Width of a thumbnail, in pixels.
+const THUMBNAIL_SIZE: u32 = 320;
+/// Space around the thumbnails and labels, in pixels.
+const PADDING: u32 = 12;
+/// Size of a pixel of the label font.
+const FONT_SCALE: u32 = 3;
+const BACKGROUND: Rgba<u8> = Rgba([22, 22, 22, 255]);
+const LABEL_COLOR: Rgba<u8> = Rgba([230, 230, 230, 255]);
+
+/// 3×5 bitmaps of the characters used in labels, one row of 3 bits per line.
+fn glyph(c: char) -> Option<[u8; 5]> {
+    Some(match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        ' ' => [0; 5],
+        _ => return None,
+    })
+}
+
+/// Draw `text` with its top left corner at `(x, y)`. Unknown characters are
+/// skipped.
+fn draw_label(image: &mut RgbaImage, text: &str, x: u32, y: u32) {
+    for (i, rows) in text.chars().filter_map(glyph).enumerate() {
+        let left = x + i as u32 * 4 * FONT_SCALE;
+        for (row, bits) in rows.iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) == 0 {
+                    continue;
+                }
+                for dy in 0..FONT_SCALE {
+                    for dx in 0..FONT_SCALE {
+                        let px = left + column * FONT_SCALE + dx;
+                        let py = y + row as u32 * FONT_SCALE + dy;
+                        if px < image.width() && py < image.height() {
+                            image.put_pixel(px, py, LABEL_COLOR);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Lay out `thumbnails` of the same size in a grid of `columns` columns, with
+/// each label written under its thumbnail.
+fn compose(thumbnails: &[RgbaImage], labels: &[String], columns: u32) -> RgbaImage {
+    let (w, h) = thumbnails.first().map_or((0, 0), |t| t.dimensions());
+    let columns = columns.max(1);
+    let rows = (thumbnails.len() as u32 + columns - 1) / columns;
+    let cell_w = w + PADDING;
+    let cell_h = h + PADDING + 5 * FONT_SCALE + PADDING;
+    let mut sheet = RgbaImage::from_pixel(
+        columns * cell_w + PADDING,
+        rows * cell_h + PADDING,
+        BACKGROUND,
+    );
+    for (i, thumbnail) in thumbnails.iter().enumerate() {
+        let (column, row) = (i as u32 % columns, i as u32 / columns);
+        let (x, y) = (PADDING + column * cell_w, PADDING + row * cell_h);
+        imageops::replace(&mut sheet, thumbnail, x, y);
+        if let Some(label) = labels.get(i) {
+            draw_label(&mut sheet, label, x, y + h + PADDING);
+        }
+    }
+    sheet
+}
+
+/// The layout of a contact sheet.
+pub(crate) struct ContactSheet {
+    columns: u32,
+    /// Whether several frames are rendered per seed, and shown in the labels.
+    several_frames: bool,
+}
+
+impl ContactSheet {
+    /// The cells of a contact sheet of `frames` for each of `seeds`, one row
+    /// per seed when there are several frames.
+    pub fn batch(seeds: &[i32], frames: &[u32]) -> Batch {
+        let cells = seeds
+            .iter()
+            .flat_map(|seed| {
+                frames.iter().map(move |frame| Cell {
+                    seed: *seed,
+                    frame: *frame,
+                })
+            })
+            .collect();
+        let columns = if frames.len() > 1 {
+            frames.len() as u32
+        } else {
+            (seeds.len() as f64).sqrt().ceil() as u32
+        };
+        let sheet = ContactSheet {
+            columns,
+            several_frames: frames.len() > 1,
+        };
+        Batch::new(cells, THUMBNAIL_SIZE, Purpose::ContactSheet(sheet))
+    }
+
+    /// Read `--contact-sheet N` and `--contact-frames F1,F2,...` from the
+    /// command line. The middle frame of the animation of `n_frames` frames is
+    /// rendered by default.
+    pub fn from_args(n_frames: u32) -> Option<Batch> {
+        let args: Vec<String> = std::env::args().collect();
+        let value = |flag: &str| {
+            args.iter()
+                .position(|a| a == flag)
+                .and_then(|i| args.get(i + 1))
+        };
+        let n_seeds = match value("--contact-sheet")?.parse::<usize>() {
+            Ok(n) if n > 0 => n,
+            _ => {
+                eprintln!("--contact-sheet expects a number of seeds");
+                return None;
+            }
+        };
+        let frames = match value("--contact-frames") {
+            Some(list) => parse_frames(list, n_frames),
+            None => vec![n_frames / 2],
+        };
+        if frames.is_empty() {
+            eprintln!("--contact-frames expects a list of frame numbers");
+            return None;
+        }
+        let seeds: Vec<i32> = (0..n_seeds).map(|_| random()).collect();
+        Some(ContactSheet::batch(&seeds, &frames))
+    }
+
+    /// Compose the sheet and save it under the current timestamp.
+    pub fn finish(&self, app: &App, cells: &[Cell], thumbnails: &[RgbaImage]) -> bool {
+        let labels: Vec<String> = cells
+            .iter()
+            .map(|cell| {
+                if self.several_frames {
+                    format!("{}/{}", cell.seed, cell.frame)
+                } else {
+                    cell.seed.to_string()
+                }
+            })
+            .collect();
+        let sheet = compose(thumbnails, &labels, self.columns);
+        let directory = contact_sheet_directory(app);
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let path = directory.join(timestamp.to_string()).with_extension("png");
+        let saved = std::fs::create_dir_all(&directory)
+            .map_err(|e| e.to_string())
+            .and_then(|_| sheet.save(&path).map_err(|e| e.to_string()));
+        match saved {
+            Ok(()) => {
+                println!("Saved contact sheet to {}", path.display());
+                true
+            }
+            Err(e) => {
+                eprintln!("Could not save the contact sheet: {}", e);
+                false
+            }
+        }
+    }
+}
+
+/// Parse a comma-separated list of frames, clamped to the animation.
+pub(crate) fn parse_frames(list: &str, n_frames: u32) -> Vec<u32> {
+    list.split(',')
+        .filter_map(|f| f.trim().parse::<u32>().ok())
+        .map(|f| f.min(n_frames - 1))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compose, parse_frames, ContactSheet, BACKGROUND, LABEL_COLOR, PADDING};
+    use crate::batch::Cell;
+    use nannou::image::{Rgba, RgbaImage};
+
+    #[test]
+    fn cells_by_seed_then_frame() {
+        let mut batch = ContactSheet::batch(&[7, -3], &[0, 30]);
+        let cell = |seed, frame| Cell { seed, frame };
+        assert_eq!(batch.next_cell(), Some((0, cell(7, 0))));
+        assert_eq!(batch.next_cell(), Some((1, cell(7, 30))));
+        assert_eq!(batch.next_cell(), Some((2, cell(-3, 0))));
+        assert!(!batch.is_rendered());
+        assert_eq!(batch.next_cell(), Some((3, cell(-3, 30))));
+        assert!(batch.is_rendered());
+        assert_eq!(batch.next_cell(), None);
+        assert_eq!(parse_frames("0, 30,x,900", 600), vec![0, 30, 599]);
+        assert!(parse_frames("x,-1", 600).is_empty());
+    }
+
+    #[test]
+    fn grid_with_labels() {
+        let red = RgbaImage::from_pixel(10, 8, Rgba([255, 0, 0, 255]));
+        let thumbnails = vec![red.clone(), red.clone(), red];
+        let labels: Vec<String> = vec!["1".into(), "-2".into(), "3".into()];
+        let sheet = compose(&thumbnails, &labels, 2);
+        let cell_h = 8 + PADDING + 15 + PADDING;
+        assert_eq!(
+            sheet.dimensions(),
+            (PADDING + 2 * (10 + PADDING), PADDING + 2 * cell_h)
+        );
+        assert_eq!(*sheet.get_pixel(PADDING, PADDING), Rgba([255, 0, 0, 255]));
+        assert_eq!(*sheet.get_pixel(0, 0), BACKGROUND);
+        // The last cell of the grid is empty.
+        assert_eq!(
+            *sheet.get_pixel(2 * PADDING + 10, PADDING + cell_h),
+            BACKGROUND
+        );
+        // The top of the "1" under the first thumbnail.
+        assert_eq!(
+            *sheet.get_pixel(PADDING + 3, PADDING + 8 + PADDING),
+            LABEL_COLOR
+        );
+    }
+}
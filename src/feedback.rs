@@ -0,0 +1,246 @@
+//! The previous frame, drawn back into the next one for trails and feedback
+//! loops.
+//!
+//! An artwork enables the feedback texture with
+//! [`Artwork::feedback_preroll`](crate::Artwork::feedback_preroll). After each
+//! frame, the drawing of its last motion blur sample is copied to the texture,
+//! before the post effects of the shader, so they don't accumulate from frame
+//! to frame. A frame drawn again, e.g. while the preview is paused, is not
+//! copied again, so it doesn't feed back into itself. A [`Trail`] draws the texture back with a transform, a fade and a
+//! blend mode:
+//!
+//! ```ignore
+//! fn feedback_preroll() -> Option<u32> {
+//!     Some(120)
+//! }
+//!
+//! fn draw_at_time(&mut self, time: f64) {
+//!     let draw = &self.base.draw;
+//!     draw.reset();
+//!     if let Some(feedback) = &self.base.feedback {
+//!         Trail::new().scale(1.01).rotate(0.002).fade(0.05).draw(draw, feedback);
+//!     }
+//!     // Draw the new content on top.
+//! }
+//! ```
+//!
+//! The content of the texture depends on the frames drawn before, so the
+//! recordings and the batch renders start by clearing the texture and drawing,
+//! without capturing them, the frames preceding the first one. A looping
+//! animation pre-rolls from the end of the loop.
+use nannou::{prelude::*, wgpu};
+
+/// The previous frame of the artwork.
+pub struct Feedback {
+    texture: wgpu::Texture,
+    /// Copies the texture drawn to, which may be multisampled, to `texture`.
+    reshaper: wgpu::TextureReshaper,
+    /// The number of frames drawn before the first captured frame.
+    preroll: u32,
+    /// The frame last copied to the texture, if any since it was cleared.
+    copied: Option<u32>,
+}
+
+impl Feedback {
+    pub(crate) fn new(device: &wgpu::Device, source: &wgpu::Texture, preroll: u32) -> Feedback {
+        let texture = wgpu::TextureBuilder::new()
+            .size(source.size())
+            .usage(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+            .format(source.format())
+            .build(device);
+        let source_view = source.view().build();
+        let reshaper = wgpu::TextureReshaper::new(
+            device,
+            &source_view,
+            source.sample_count(),
+            source.sample_type(),
+            1,
+            texture.format(),
+        );
+        Feedback {
+            texture,
+            reshaper,
+            preroll,
+            copied: None,
+        }
+    }
+
+    /// The texture holding the previous frame.
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    pub(crate) fn preroll(&self) -> u32 {
+        self.preroll
+    }
+
+    /// Clear the texture to transparent.
+    pub(crate) fn encode_clear(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        self.copied = None;
+        let view = self.texture.view().build();
+        // The default load operation clears the attachment.
+        wgpu::RenderPassBuilder::new()
+            .color_attachment(&view, |color| color)
+            .begin(encoder);
+    }
+
+    /// Copy `frame`, just drawn, to the texture, unless it was the last frame
+    /// copied.
+    pub(crate) fn encode_copy(&mut self, encoder: &mut wgpu::CommandEncoder, frame: u32) {
+        if self.copied == Some(frame) {
+            return;
+        }
+        self.copied = Some(frame);
+        let view = self.texture.view().build();
+        self.reshaper.encode_render_pass(&view, encoder);
+    }
+}
+
+/// The frames drawn before `frame` to pre-roll a loop of `n_frames` frames,
+/// in order.
+pub(crate) fn preroll_frames(frame: u32, preroll: u32, n_frames: u32) -> Vec<u32> {
+    (1..=preroll.min(n_frames))
+        .rev()
+        .map(|i| (frame + n_frames - i) % n_frames)
+        .collect()
+}
+
+/// A step of a pre-roll.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PrerollStep {
+    /// Clear the texture, so that nothing drawn before the pre-roll shows.
+    Clear,
+    /// Draw a frame without capturing it.
+    Frame(u32),
+}
+
+/// The steps of the pre-roll of `frame` in a loop of `n_frames` frames: the
+/// texture is cleared, then the `preroll` frames before `frame` are drawn.
+pub(crate) fn preroll_steps(frame: u32, preroll: u32, n_frames: u32) -> Vec<PrerollStep> {
+    std::iter::once(PrerollStep::Clear)
+        .chain(
+            preroll_frames(frame, preroll, n_frames)
+                .into_iter()
+                .map(PrerollStep::Frame),
+        )
+        .collect()
+}
+
+/// How the previous frame is drawn back.
+#[derive(Clone, Debug)]
+pub struct Trail {
+    scale: f32,
+    rotation: f32,
+    offset: Vec2,
+    fade: f32,
+    fade_color: Srgb,
+    blend: wgpu::BlendComponent,
+}
+
+impl Default for Trail {
+    fn default() -> Trail {
+        Trail {
+            scale: 1.,
+            rotation: 0.,
+            offset: Vec2::ZERO,
+            fade: 0.,
+            fade_color: srgb(0., 0., 0.),
+            blend: BLEND_NORMAL,
+        }
+    }
+}
+
+impl Trail {
+    pub fn new() -> Trail {
+        Trail::default()
+    }
+
+    /// Scale the previous frame around the center.
+    pub fn scale(mut self, scale: f32) -> Trail {
+        self.scale = scale;
+        self
+    }
+
+    /// Rotate the previous frame around the center, in radians.
+    pub fn rotate(mut self, rotation: f32) -> Trail {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Move the previous frame, in pixels.
+    pub fn offset(mut self, offset: Vec2) -> Trail {
+        self.offset = offset;
+        self
+    }
+
+    /// Fade the previous frame towards `color` by `amount`, between 0 and 1.
+    pub fn fade(mut self, amount: f32) -> Trail {
+        self.fade = amount;
+        self
+    }
+
+    pub fn fade_color(mut self, color: Srgb) -> Trail {
+        self.fade_color = color;
+        self
+    }
+
+    /// The blend mode of the previous frame, e.g. `BLEND_ADD`.
+    pub fn blend(mut self, blend: wgpu::BlendComponent) -> Trail {
+        self.blend = blend;
+        self
+    }
+
+    /// Draw the previous frame, and the fade over it. Anything drawn before is
+    /// covered, so the trail is usually drawn first.
+    pub fn draw(&self, draw: &Draw, feedback: &Feedback) {
+        let [w, h] = feedback.texture.size();
+        draw.color_blend(self.blend)
+            .translate(self.offset.extend(0.))
+            .rotate(self.rotation)
+            .scale(self.scale)
+            .texture(&feedback.texture)
+            .w_h(w as f32, h as f32);
+        if self.fade > 0. {
+            let c = self.fade_color;
+            draw.rect()
+                .w_h(w as f32, h as f32)
+                .color(srgba(c.red, c.green, c.blue, self.fade));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{preroll_frames, preroll_steps, PrerollStep};
+
+    #[test]
+    fn preroll_from_the_end_of_the_loop() {
+        assert_eq!(preroll_frames(0, 3, 600), vec![597, 598, 599]);
+        assert_eq!(preroll_frames(10, 3, 600), vec![7, 8, 9]);
+        assert_eq!(preroll_frames(1, 3, 600), vec![598, 599, 0]);
+        // The pre-roll never exceeds the loop.
+        assert_eq!(preroll_frames(0, 10, 4), vec![0, 1, 2, 3]);
+        assert!(preroll_frames(5, 0, 600).is_empty());
+    }
+
+    #[test]
+    fn prerolls_do_not_depend_on_the_previous_state() {
+        // A feedback loop on the CPU, where each frame halves the previous one
+        // and adds its number.
+        let run = |mut texture: f64, steps: &[PrerollStep]| {
+            for step in steps {
+                texture = match step {
+                    PrerollStep::Clear => 0.,
+                    PrerollStep::Frame(frame) => 0.5 * texture + *frame as f64,
+                };
+            }
+            texture
+        };
+        let steps = preroll_steps(10, 3, 600);
+        assert_eq!(steps[0], PrerollStep::Clear);
+        assert_eq!(run(0., &steps), run(123., &steps));
+        // Without the clear, the previous state shows through.
+        assert_ne!(run(0., &steps[1..]), run(123., &steps[1..]));
+        assert_eq!(preroll_steps(5, 0, 600), vec![PrerollStep::Clear]);
+    }
+}
@@ -0,0 +1,467 @@
+//! Image sequences and videos used as textures that change with time.
+//!
+//! Footage is declared in [`Options::footage`](crate::Options::footage), as
+//! paths relative to `assets/`. A directory is an image sequence, whose frames
+//! are ordered by number when their names are numbers (like the frames of a
+//! recording), or by name otherwise. Any other file is a video, decoded once
+//! with the `ffmpeg` found in the `PATH`. Footage that can't be opened, or
+//! whose frames do not all have the same size, is reported and shown as a
+//! transparent texture.
+//!
+//! The footage plays at its own frame rate: the one found by `ffprobe` for
+//! videos, and [`FPS`](crate::FPS), the rate of recordings, for image
+//! sequences unless set with [`Footage::set_fps`]. Footage shorter than the
+//! animation loops, and longer footage is cut. With
+//! [`Footage::set_stretched`], the footage spans the whole animation instead,
+//! whatever its length. Either way its texture shows the frame at the time
+//! being drawn blended with the next frame for the fraction in between, so it
+//! follows motion blur sub-samples too, and the last frame is blended back
+//! into the first one.
+//!
+//! ```ignore
+//! fn new(mut base: BaseModel) -> Model {
+//!     base.footage[0].set_stretched(true);
+//!     Model { base }
+//! }
+//!
+//! fn draw_at_time(&mut self, time: f64) {
+//!     let draw = &self.base.draw;
+//!     draw.texture(self.base.footage[0].texture()).w_h(1., 1.);
+//! }
+//! ```
+use nannou::{
+    image::{self, RgbaImage},
+    wgpu,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The number of decoded frames kept in memory.
+const CACHE_SIZE: usize = 4;
+
+/// The frames to show at `position`, counted in frames from the first one, in
+/// footage of `n_frames` frames looping over, and how much of the second frame
+/// to blend into the first.
+fn frames_at(position: f64, n_frames: usize) -> (usize, usize, f32) {
+    let position = position.rem_euclid(n_frames as f64);
+    let first = (position.floor() as usize).min(n_frames - 1);
+    (first, (first + 1) % n_frames, position.fract() as f32)
+}
+
+/// The position at `time`, in frames, of footage of `n_frames` frames playing
+/// at `fps` in an animation lasting `duration` seconds, or stretched over it.
+fn position(time: f64, duration: f64, fps: f64, n_frames: usize, stretched: bool) -> f64 {
+    if stretched {
+        time * n_frames as f64
+    } else {
+        time * duration * fps
+    }
+}
+
+/// Parse a frame rate printed by `ffprobe`, like `30000/1001` or `25`.
+fn parse_frame_rate(rate: &str) -> Option<f64> {
+    let rate = match rate.trim().split_once('/') {
+        Some((numerator, denominator)) => {
+            numerator.parse::<f64>().ok()? / denominator.parse::<f64>().ok()?
+        }
+        None => rate.trim().parse().ok()?,
+    };
+    if rate.is_finite() && rate > 0. {
+        Some(rate)
+    } else {
+        None
+    }
+}
+
+/// The frame rate of the first video stream of `video`, with the `ffprobe`
+/// found in the `PATH`.
+fn frame_rate(video: &Path) -> Result<f64, Box<dyn Error>> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0"])
+        .args(["-show_entries", "stream=r_frame_rate", "-of", "csv=p=0"])
+        .arg(video)
+        .output()
+        .map_err(|e| format!("could not run ffprobe: {}", e))?;
+    if !output.status.success() {
+        return Err("ffprobe failed".into());
+    }
+    let rate = String::from_utf8_lossy(&output.stdout);
+    parse_frame_rate(&rate).ok_or_else(|| format!("invalid frame rate {:?}", rate.trim()).into())
+}
+
+/// Check that all the `frames` have the size of the first one, reading only
+/// their headers.
+fn check_sizes(frames: &[PathBuf], size: (u32, u32)) -> Result<(), Box<dyn Error>> {
+    for frame in frames {
+        let frame_size = image::image_dimensions(frame)?;
+        if frame_size != size {
+            return Err(format!(
+                "{} is {}x{} while the first frame is {}x{}",
+                frame.display(),
+                frame_size.0,
+                frame_size.1,
+                size.0,
+                size.1
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Blend `b` into `a` by `amount`, between 0 and 1.
+fn blend(a: &RgbaImage, b: &RgbaImage, amount: f32) -> RgbaImage {
+    let mut blended = a.clone();
+    for (pixel, other) in blended.pixels_mut().zip(b.pixels()) {
+        for c in 0..4 {
+            let (x, y) = (pixel[c] as f32, other[c] as f32);
+            pixel[c] = (x + (y - x) * amount).round() as u8;
+        }
+    }
+    blended
+}
+
+/// The order of the frames of an image sequence: numbered frames first, by
+/// number, then the others by name.
+fn frame_order(path: &Path) -> (bool, u64, String) {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let number: Option<u64> = stem.parse().ok();
+    (number.is_none(), number.unwrap_or(0), stem.into_owned())
+}
+
+/// The images in `directory`, in the order of the sequence.
+fn image_sequence(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut frames: Vec<PathBuf> = fs::read_dir(directory)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+            matches!(extension.as_deref(), Some("png" | "jpg" | "jpeg"))
+        })
+        .collect();
+    frames.sort_by_key(|path| frame_order(path));
+    Ok(frames)
+}
+
+/// Decode `video` to an image sequence in the temporary directory, unless it
+/// was already decoded since it was last modified, and return the directory.
+fn decode_video(video: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let modified = fs::metadata(video)?
+        .modified()?
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    let name = video.file_name().unwrap_or_default().to_string_lossy();
+    let directory = std::env::temp_dir()
+        .join("artworks-footage")
+        .join(format!("{}-{}", name, modified));
+    decode_once(&directory, |staging| {
+        let status = Command::new("ffmpeg")
+            .args(["-loglevel", "error", "-i"])
+            .arg(video)
+            .arg(staging.join("%06d.png"))
+            .status()
+            .map_err(|e| format!("could not run ffmpeg: {}", e))?;
+        if !status.success() {
+            return Err("ffmpeg failed".into());
+        }
+        Ok(())
+    })?;
+    Ok(directory)
+}
+
+/// Fill `directory` with `decode`, unless it exists. The frames are decoded
+/// in a staging directory which is only renamed to `directory` once `decode`
+/// succeeded, so an interrupted decoding is never taken for a finished one.
+fn decode_once(
+    directory: &Path,
+    decode: impl FnOnce(&Path) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    if directory.exists() {
+        return Ok(());
+    }
+    let mut staging = directory.as_os_str().to_owned();
+    staging.push(".partial");
+    let staging = PathBuf::from(staging);
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+    match decode(&staging) {
+        Ok(()) => Ok(fs::rename(&staging, directory)?),
+        Err(e) => {
+            fs::remove_dir_all(&staging).ok();
+            Err(e)
+        }
+    }
+}
+
+/// An image sequence or a video, shown in a texture.
+pub struct Footage {
+    frames: Vec<PathBuf>,
+    /// Recently decoded frames, by index.
+    cache: HashMap<usize, RgbaImage>,
+    texture: wgpu::Texture,
+    /// The frames and blend amount shown in the texture.
+    shown: Option<(usize, usize, f32)>,
+    /// The frame rate the footage plays at.
+    fps: f64,
+    /// Whether the footage spans the whole animation, whatever its length.
+    stretched: bool,
+}
+
+impl Footage {
+    /// Open the image sequence in the directory `path`, or decode the video
+    /// `path`.
+    pub fn open(device: &wgpu::Device, path: &Path) -> Result<Footage, Box<dyn Error>> {
+        let (directory, fps) = if path.is_dir() {
+            (path.to_path_buf(), crate::FPS as f64)
+        } else if path.exists() {
+            (decode_video(path)?, frame_rate(path)?)
+        } else {
+            return Err(format!("{} does not exist", path.display()).into());
+        };
+        let frames = image_sequence(&directory)?;
+        let first = match frames.first() {
+            Some(first) => image::open(first)?.to_rgba8(),
+            None => return Err(format!("no frames found in {}", directory.display()).into()),
+        };
+        check_sizes(&frames, first.dimensions())?;
+        let texture = wgpu::TextureBuilder::new()
+            .size([first.width(), first.height()])
+            .format(wgpu::TextureFormat::Rgba8UnormSrgb)
+            .usage(wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST)
+            .build(device);
+        let mut cache = HashMap::new();
+        cache.insert(0, first);
+        Ok(Footage {
+            frames,
+            cache,
+            texture,
+            shown: None,
+            fps,
+            stretched: false,
+        })
+    }
+
+    /// Footage without frames, shown as a transparent texture, standing in for
+    /// footage that could not be opened.
+    pub fn missing(device: &wgpu::Device) -> Footage {
+        let texture = wgpu::TextureBuilder::new()
+            .size([1, 1])
+            .format(wgpu::TextureFormat::Rgba8UnormSrgb)
+            .usage(wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST)
+            .build(device);
+        Footage {
+            frames: vec![],
+            cache: HashMap::new(),
+            texture,
+            shown: None,
+            fps: crate::FPS as f64,
+            stretched: false,
+        }
+    }
+
+    /// Play the footage at `fps` frames per second, e.g. for an image
+    /// sequence that was not recorded at [`FPS`](crate::FPS).
+    pub fn set_fps(&mut self, fps: f64) {
+        self.fps = fps;
+        self.shown = None;
+    }
+
+    /// Stretch the footage over the whole animation, whatever its length and
+    /// frame rate, or play it at its frame rate.
+    pub fn set_stretched(&mut self, stretched: bool) {
+        self.stretched = stretched;
+        self.shown = None;
+    }
+
+    /// The number of frames of the footage.
+    pub fn n_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// The texture showing the footage at the time being drawn.
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    fn frame(&mut self, index: usize) -> Result<&RgbaImage, image::ImageError> {
+        if !self.cache.contains_key(&index) {
+            let path = self.frames.get(index).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, "the footage has no frames")
+            })?;
+            let image = image::open(path)?.to_rgba8();
+            if self.cache.len() >= CACHE_SIZE {
+                self.cache.clear();
+            }
+            self.cache.insert(index, image);
+        }
+        Ok(&self.cache[&index])
+    }
+
+    fn position(&self, time: f64, duration: f64) -> f64 {
+        position(time, duration, self.fps, self.n_frames(), self.stretched)
+    }
+
+    /// The footage at `time`, between 0 and 1, in an animation lasting
+    /// `duration` seconds.
+    pub fn image_at(&mut self, time: f64, duration: f64) -> Result<RgbaImage, image::ImageError> {
+        let position = self.position(time, duration);
+        let (first, second, amount) = frames_at(position, self.n_frames());
+        let image = self.frame(first)?.clone();
+        if amount == 0. {
+            return Ok(image);
+        }
+        Ok(blend(&image, self.frame(second)?, amount))
+    }
+
+    /// Show the footage at `time` in the texture, as part of the commands
+    /// recorded in `encoder`, for an animation lasting `duration` seconds.
+    /// Errors are reported and the texture is kept as it is.
+    pub(crate) fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        time: f64,
+        duration: f64,
+    ) {
+        if self.frames.is_empty() {
+            return;
+        }
+        let shown = frames_at(self.position(time, duration), self.n_frames());
+        if self.shown == Some(shown) {
+            return;
+        }
+        match self.image_at(time, duration) {
+            Ok(image) => {
+                self.texture.upload_data(device, encoder, image.as_raw());
+                self.shown = Some(shown);
+            }
+            Err(e) => eprintln!("Could not read the footage: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        blend, check_sizes, decode_once, frame_order, frames_at, parse_frame_rate, position,
+    };
+    use nannou::image::{Rgba, RgbaImage};
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    #[test]
+    fn frames_loop_over_the_footage() {
+        assert_eq!(frames_at(0., 10), (0, 1, 0.));
+        let (first, second, amount) = frames_at(2.5, 10);
+        assert_eq!((first, second), (2, 3));
+        assert!((amount - 0.5).abs() < 1e-6);
+        // The last frame blends into the first one.
+        assert_eq!(frames_at(9.5, 10).1, 0);
+        // Footage shorter than the animation loops.
+        assert_eq!(frames_at(10., 10), (0, 1, 0.));
+        assert_eq!(frames_at(23., 10), (3, 4, 0.));
+    }
+
+    #[test]
+    fn footage_plays_at_its_frame_rate() {
+        // 24 fps footage of 100 frames, in a 10 seconds animation.
+        assert_eq!(position(0.5, 10., 24., 100, false), 120.);
+        assert_eq!(
+            frames_at(position(0.5, 10., 24., 100, false), 100),
+            (20, 21, 0.)
+        );
+        // Stretched, it spans the animation whatever its frame rate.
+        assert_eq!(position(0.5, 10., 24., 100, true), 50.);
+        assert_eq!(
+            frames_at(position(1., 10., 24., 100, true), 100),
+            (0, 1, 0.)
+        );
+    }
+
+    #[test]
+    fn ffprobe_frame_rates() {
+        assert_eq!(parse_frame_rate("25\n"), Some(25.));
+        let ntsc = parse_frame_rate("30000/1001").unwrap();
+        assert!((ntsc - 29.97).abs() < 1e-3);
+        assert_eq!(parse_frame_rate("0/0"), None);
+        assert_eq!(parse_frame_rate(""), None);
+    }
+
+    #[test]
+    fn frames_of_another_size() {
+        let directory =
+            std::env::temp_dir().join(format!("artworks-footage-size-test-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let frames: Vec<PathBuf> = [(4, 4), (4, 4), (4, 2)]
+            .iter()
+            .enumerate()
+            .map(|(i, &(w, h))| {
+                let path = directory.join(format!("{}.png", i));
+                RgbaImage::new(w, h).save(&path).unwrap();
+                path
+            })
+            .collect();
+        assert!(check_sizes(&frames[..2], (4, 4)).is_ok());
+        let error = check_sizes(&frames, (4, 4)).unwrap_err().to_string();
+        assert!(error.contains("2.png is 4x2"), "{}", error);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn blend_frames() {
+        let a = RgbaImage::from_pixel(2, 2, Rgba([0, 100, 200, 255]));
+        let b = RgbaImage::from_pixel(2, 2, Rgba([100, 100, 0, 255]));
+        assert_eq!(
+            *blend(&a, &b, 0.25).get_pixel(1, 1),
+            Rgba([25, 100, 150, 255])
+        );
+    }
+
+    #[test]
+    fn numbered_frames_in_order() {
+        let mut frames: Vec<PathBuf> = ["10.png", "b.png", "9.png", "a.jpg", "0.png"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        frames.sort_by_key(|path| frame_order(path));
+        let names: Vec<&Path> = frames.iter().map(PathBuf::as_path).collect();
+        assert_eq!(
+            names,
+            ["0.png", "9.png", "10.png", "a.jpg", "b.png"]
+                .iter()
+                .map(Path::new)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn interrupted_decoding_is_not_reused() {
+        let directory = std::env::temp_dir()
+            .join(format!("artworks-footage-test-{}", std::process::id()))
+            .join("video.mp4-1");
+        let staging = directory.with_file_name("video.mp4-1.partial");
+        // A decoding interrupted before, and one that fails now.
+        fs::create_dir_all(&staging).unwrap();
+        fs::write(staging.join("000001.png"), "").unwrap();
+        assert!(decode_once(&directory, |_| Err("ffmpeg failed".into())).is_err());
+        assert!(!directory.exists() && !staging.exists());
+
+        decode_once(&directory, |staging| {
+            assert_eq!(fs::read_dir(staging)?.count(), 0);
+            Ok(fs::write(staging.join("000001.png"), "")?)
+        })
+        .unwrap();
+        assert!(directory.join("000001.png").exists() && !staging.exists());
+        // Finished decodings are reused.
+        decode_once(&directory, |_| panic!("decoded twice")).unwrap();
+        fs::remove_dir_all(directory.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,290 @@
+//! Write the recorded frames to PNG files on a pool of worker threads.
+//!
+//! At most `--writer-queue` frames are pending at once, counting the frames
+//! still being read back from the GPU, the frames waiting in the queue and the
+//! frames being encoded. Once the queue is full, the rendering waits for a
+//! frame to be written, so a 4K recording uses a bounded amount of memory.
+//! The number of threads encoding frames and the compression level are set
+//! with `--writer-threads` and `--png-compression fast|default|best`:
+//!
+//! ``` sh
+//! cargo run --release --example tiling -- --writer-threads 8 --png-compression fast
+//! ```
+//!
+//! Errors are collected rather than panicking on the worker threads, and
+//! reported by the recorder.
+use nannou::{
+    image::{
+        codecs::png::{CompressionType, FilterType, PngEncoder},
+        ColorType, RgbaImage,
+    },
+    wgpu,
+};
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{sync_channel, Receiver, SyncSender},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// How often the GPU is polled while waiting for room in the queue.
+const POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+/// The settings of the frame writer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct WriterConfig {
+    /// The number of threads encoding frames.
+    pub threads: usize,
+    /// The largest number of frames pending at once.
+    pub queue: usize,
+    pub compression: CompressionType,
+}
+
+impl Default for WriterConfig {
+    fn default() -> WriterConfig {
+        WriterConfig {
+            threads: thread::available_parallelism().map_or(4, |n| n.get()),
+            queue: 16,
+            compression: CompressionType::Fast,
+        }
+    }
+}
+
+impl WriterConfig {
+    /// Read `--writer-threads N`, `--writer-queue N` and
+    /// `--png-compression LEVEL` from the command line.
+    pub fn from_args() -> WriterConfig {
+        let args: Vec<String> = std::env::args().collect();
+        let value = |flag: &str| {
+            args.iter()
+                .position(|a| a == flag)
+                .and_then(|i| args.get(i + 1))
+        };
+        let mut config = WriterConfig::default();
+        let count = |flag: &str, default: usize| match value(flag).map(|v| v.parse::<usize>()) {
+            Some(Ok(n)) if n > 0 => n,
+            Some(_) => {
+                eprintln!("{} expects a positive number", flag);
+                default
+            }
+            None => default,
+        };
+        config.threads = count("--writer-threads", config.threads);
+        config.queue = count("--writer-queue", config.queue);
+        config.compression = match value("--png-compression").map(String::as_str) {
+            Some("fast") | None => CompressionType::Fast,
+            Some("default") => CompressionType::Default,
+            Some("best") => CompressionType::Best,
+            Some(level) => {
+                eprintln!(
+                    "Unknown PNG compression {}, use fast, default or best",
+                    level
+                );
+                config.compression
+            }
+        };
+        config
+    }
+}
+
+fn write_png(path: &Path, image: &RgbaImage, compression: CompressionType) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    PngEncoder::new_with_quality(BufWriter::new(file), compression, FilterType::Sub)
+        .encode(image, image.width(), image.height(), ColorType::Rgba8)
+        .map_err(|e| e.to_string())
+}
+
+struct Job {
+    path: PathBuf,
+    image: RgbaImage,
+}
+
+/// The state shared by the writer, its workers and the read-back callbacks.
+#[derive(Default)]
+struct Shared {
+    /// The number of pending frames.
+    pending: Mutex<usize>,
+    /// Signalled when a pending frame is written or given up.
+    released: Condvar,
+    errors: Mutex<Vec<String>>,
+}
+
+impl Shared {
+    fn release(&self) {
+        *self.pending.lock().unwrap() -= 1;
+        self.released.notify_all();
+    }
+
+    /// Give up a pending frame.
+    fn fail(&self, error: String) {
+        self.errors.lock().unwrap().push(error);
+        self.release();
+    }
+
+    /// Wait until `done` holds for the number of pending frames, calling
+    /// `poll` regularly, and return the locked number.
+    fn wait(&self, done: impl Fn(usize) -> bool, mut poll: impl FnMut()) -> MutexGuard<'_, usize> {
+        loop {
+            let pending = self.pending.lock().unwrap();
+            if done(*pending) {
+                return pending;
+            }
+            drop(self.released.wait_timeout(pending, POLL_INTERVAL).unwrap());
+            poll();
+        }
+    }
+}
+
+/// Send a frame to the workers.
+fn push(sender: &SyncSender<Job>, shared: &Shared, path: PathBuf, image: RgbaImage) {
+    if sender.send(Job { path, image }).is_err() {
+        shared.fail("The frame writer is stopped".into());
+    }
+}
+
+pub(crate) struct FrameWriter {
+    capacity: usize,
+    sender: Option<SyncSender<Job>>,
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl FrameWriter {
+    pub fn new(config: WriterConfig) -> FrameWriter {
+        let (sender, receiver) = sync_channel(config.queue);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let shared = Arc::new(Shared::default());
+        let workers = (0..config.threads)
+            .map(|i| {
+                let receiver: Arc<Mutex<Receiver<Job>>> = receiver.clone();
+                let shared = shared.clone();
+                thread::Builder::new()
+                    .name(format!("frame writer {}", i))
+                    .spawn(move || loop {
+                        // The lock is released as soon as a job is received.
+                        let job = match receiver.lock().unwrap().recv() {
+                            Ok(job) => job,
+                            Err(_) => break,
+                        };
+                        match write_png(&job.path, &job.image, config.compression) {
+                            Ok(()) => shared.release(),
+                            Err(e) => shared.fail(format!(
+                                "Could not write {}: {}",
+                                job.path.display(),
+                                e
+                            )),
+                        }
+                    })
+                    .expect("failed to spawn a frame writer thread")
+            })
+            .collect();
+        FrameWriter {
+            capacity: config.queue,
+            sender: Some(sender),
+            shared,
+            workers,
+        }
+    }
+
+    /// Reserve room for a frame, waiting for pending frames to be written if
+    /// the queue is full. `poll` is called while waiting, so that the frames
+    /// being read back from the GPU make progress.
+    pub fn reserve(&self, poll: impl FnMut()) {
+        *self.shared.wait(|pending| pending < self.capacity, poll) += 1;
+    }
+
+    /// Queue an image in the room reserved with [`FrameWriter::reserve`].
+    pub fn write_image(&self, path: PathBuf, image: RgbaImage) {
+        push(self.sender(), &self.shared, path, image);
+    }
+
+    /// Queue the frame captured by `snapshot` in the room reserved with
+    /// [`FrameWriter::reserve`], once it is read back from the GPU.
+    pub fn write(&self, path: PathBuf, snapshot: wgpu::TextueSnapshot) {
+        let sender = self.sender().clone();
+        let shared = self.shared.clone();
+        let read = snapshot.read(move |result| match result {
+            Ok(image) => push(&sender, &shared, path, image.to_owned()),
+            Err(e) => shared.fail(format!("Could not read the frame from the GPU: {}", e)),
+        });
+        if let Err(e) = read {
+            self.shared
+                .fail(format!("Could not read the frame from the GPU: {:?}", e));
+        }
+    }
+
+    fn sender(&self) -> &SyncSender<Job> {
+        self.sender.as_ref().expect("the frame writer is stopped")
+    }
+
+    /// The number of frames not written yet.
+    pub fn pending(&self) -> usize {
+        *self.shared.pending.lock().unwrap()
+    }
+
+    /// The errors that happened since the last call.
+    pub fn take_errors(&self) -> Vec<String> {
+        std::mem::take(&mut *self.shared.errors.lock().unwrap())
+    }
+
+    /// Wait until all the pending frames are written, calling `poll` while
+    /// waiting.
+    pub fn flush(&self, poll: impl FnMut()) {
+        drop(self.shared.wait(|pending| pending == 0, poll));
+    }
+}
+
+impl Drop for FrameWriter {
+    fn drop(&mut self) {
+        // The workers stop once the queue is empty and closed.
+        self.sender = None;
+        for worker in self.workers.drain(..) {
+            worker.join().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FrameWriter, WriterConfig};
+    use nannou::image::{self, codecs::png::CompressionType, Rgba, RgbaImage};
+    use std::fs;
+
+    #[test]
+    fn write_with_a_bounded_queue() {
+        let directory =
+            std::env::temp_dir().join(format!("artworks-frame-writer-test-{}", std::process::id()));
+        fs::remove_dir_all(&directory).ok();
+        fs::create_dir_all(&directory).unwrap();
+        let writer = FrameWriter::new(WriterConfig {
+            threads: 2,
+            queue: 3,
+            compression: CompressionType::Fast,
+        });
+        for i in 0..10u8 {
+            writer.reserve(|| {});
+            assert!(writer.pending() <= 3);
+            let image = RgbaImage::from_pixel(64, 64, Rgba([i, 0, 0, 255]));
+            writer.write_image(directory.join(format!("{}.png", i)), image);
+        }
+        // A frame that can't be written is reported.
+        writer.reserve(|| {});
+        writer.write_image(directory.join("missing/0.png"), RgbaImage::new(1, 1));
+        writer.flush(|| {});
+        assert_eq!(writer.pending(), 0);
+        let errors = writer.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("missing"));
+        assert!(writer.take_errors().is_empty());
+
+        let image = image::open(directory.join("7.png")).unwrap().to_rgba8();
+        assert_eq!(*image.get_pixel(10, 10), Rgba([7, 0, 0, 255]));
+        drop(writer);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,274 @@
+//! Golden-image regression tests of the artworks.
+//!
+//! With `--golden`, an artwork renders a few frames at a fixed seed, and small
+//! copies of them are compared with the reference images stored in
+//! `tests/golden/<artwork name>/`. When a frame differs, the rendered frame and
+//! an image highlighting the differences in red are written next to its
+//! reference, and the process exits with an error. `--golden-update` writes
+//! the references instead:
+//!
+//! ``` sh
+//! cargo run --release --example tiling -- --golden-update
+//! cargo run --release --example tiling -- --golden --golden-frames 0,300
+//! ```
+//!
+//! The frames are rendered at the size of the compared images rather than in
+//! 4K. Artworks that build random state in `new` must build it again from
+//! the seed in [`Artwork::reseed`](crate::Artwork::reseed), which is called
+//! before each frame, to render the same frames at each run.
+//!
+//! `./golden_test.sh` runs the test of every artwork with references. The
+//! tests need a GPU and open a window, so `cargo test` only runs them when
+//! asked to:
+//!
+//! ``` sh
+//! cargo test --test golden_images -- --ignored
+//! ```
+use crate::{
+    batch::{Batch, Cell, Purpose},
+    contact_sheet::parse_frames,
+    golden_directory,
+};
+use nannou::{
+    image::{self, Rgba, RgbaImage},
+    prelude::*,
+};
+use std::fs;
+
+/// The seed used to render the frames.
+const GOLDEN_SEED: i32 = 1;
+/// The width of the rendered and compared images, in pixels.
+pub(crate) const IMAGE_SIZE: u32 = 256;
+/// The CIELAB color difference above which two pixels look different. A
+/// difference of about 2.3 is just noticeable.
+const MAX_DELTA_E: f32 = 5.;
+/// The fraction of pixels allowed to differ, to tolerate antialiasing.
+const MAX_DIFFERING: f32 = 0.005;
+
+/// How much an image differs from its reference.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Comparison {
+    /// The fraction of pixels that look different.
+    pub differing: f32,
+    /// The largest color difference, in CIELAB ΔE.
+    pub max_delta_e: f32,
+}
+
+impl Comparison {
+    pub fn passes(&self) -> bool {
+        self.differing <= MAX_DIFFERING
+    }
+}
+
+/// Convert an sRGB color to CIELAB, under a D65 illuminant.
+fn lab(color: &Rgba<u8>) -> [f32; 3] {
+    let linear = |c: u8| {
+        let c = c as f32 / 255.;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let [r, g, b] = [linear(color[0]), linear(color[1]), linear(color[2])];
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f32| {
+        if t > 216. / 24389. {
+            t.cbrt()
+        } else {
+            (24389. / 27. * t + 16.) / 116.
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116. * fy - 16., 500. * (fx - fy), 200. * (fy - fz)]
+}
+
+/// The perceptual difference between two colors.
+fn delta_e(a: &Rgba<u8>, b: &Rgba<u8>) -> f32 {
+    let (a, b) = (lab(a), lab(b));
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+/// Compare two images, or return `None` if their sizes differ.
+pub fn compare(reference: &RgbaImage, actual: &RgbaImage) -> Option<Comparison> {
+    if reference.dimensions() != actual.dimensions() {
+        return None;
+    }
+    let (mut differing, mut max_delta_e) = (0, 0f32);
+    for (a, b) in reference.pixels().zip(actual.pixels()) {
+        let delta = delta_e(a, b);
+        max_delta_e = max_delta_e.max(delta);
+        if delta > MAX_DELTA_E {
+            differing += 1;
+        }
+    }
+    Some(Comparison {
+        differing: differing as f32 / reference.pixels().len().max(1) as f32,
+        max_delta_e,
+    })
+}
+
+/// A dimmed copy of `actual`, with the pixels that differ from `reference`
+/// in red.
+pub fn diff_image(reference: &RgbaImage, actual: &RgbaImage) -> RgbaImage {
+    RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+        let pixel = actual.get_pixel(x, y);
+        let differs = x >= reference.width()
+            || y >= reference.height()
+            || delta_e(reference.get_pixel(x, y), pixel) > MAX_DELTA_E;
+        if differs {
+            Rgba([255, 0, 0, 255])
+        } else {
+            let luma =
+                (0.2126 * pixel[0] as f32 + 0.7152 * pixel[1] as f32 + 0.0722 * pixel[2] as f32)
+                    / 3.;
+            Rgba([luma as u8, luma as u8, luma as u8, 255])
+        }
+    })
+}
+
+/// Whether a golden-image test or an update of the references is asked for
+/// on the command line.
+pub(crate) fn requested() -> bool {
+    std::env::args().any(|a| a == "--golden" || a == "--golden-update")
+}
+
+/// A golden-image test of an artwork.
+pub(crate) struct Golden {
+    /// Whether to write the references instead of comparing with them.
+    update: bool,
+}
+
+impl Golden {
+    /// Read `--golden` or `--golden-update`, and `--golden-frames F1,F2,...`
+    /// from the command line. Four frames evenly spread over the animation of
+    /// `n_frames` frames are rendered by default.
+    pub fn from_args(n_frames: u32) -> Option<Batch> {
+        if !requested() {
+            return None;
+        }
+        let args: Vec<String> = std::env::args().collect();
+        let update = args.iter().any(|a| a == "--golden-update");
+        let frames = match args.iter().position(|a| a == "--golden-frames") {
+            Some(i) => parse_frames(args.get(i + 1).map_or("", |f| f.as_str()), n_frames),
+            None => (0..4).map(|i| i * n_frames / 4).collect(),
+        };
+        if frames.is_empty() {
+            eprintln!("--golden-frames expects a list of frame numbers");
+            std::process::exit(1);
+        }
+        let cells = frames
+            .into_iter()
+            .map(|frame| Cell {
+                seed: GOLDEN_SEED,
+                frame,
+            })
+            .collect();
+        Some(Batch::new(
+            cells,
+            IMAGE_SIZE,
+            Purpose::Golden(Golden { update }),
+        ))
+    }
+
+    /// Compare the rendered frames with their references, or update them.
+    pub fn finish(&self, app: &App, cells: &[Cell], images: &[RgbaImage]) -> bool {
+        let directory = golden_directory(app);
+        if let Err(e) = fs::create_dir_all(&directory) {
+            eprintln!("Could not create {}: {}", directory.display(), e);
+            return false;
+        }
+        let mut success = true;
+        for (cell, image) in cells.iter().zip(images) {
+            let path = directory.join(format!("{}.png", cell.frame));
+            if self.update {
+                match image.save(&path) {
+                    Ok(()) => println!("Updated {}", path.display()),
+                    Err(e) => {
+                        eprintln!("Could not write {}: {}", path.display(), e);
+                        success = false;
+                    }
+                }
+                continue;
+            }
+            let reference = match image::open(&path) {
+                Ok(reference) => reference.to_rgba8(),
+                Err(e) => {
+                    eprintln!("Could not read {}: {}", path.display(), e);
+                    success = false;
+                    continue;
+                }
+            };
+            match compare(&reference, image) {
+                Some(comparison) if comparison.passes() => {
+                    println!("Frame {} matches its reference", cell.frame);
+                }
+                comparison => {
+                    success = false;
+                    match comparison {
+                        Some(c) => eprintln!(
+                            "Frame {} differs from its reference: {:.2}% of the pixels differ, up to ΔE {:.1}",
+                            cell.frame,
+                            100. * c.differing,
+                            c.max_delta_e
+                        ),
+                        None => eprintln!("Frame {} does not have the size of its reference", cell.frame),
+                    }
+                    let actual = directory.join(format!("{}-actual.png", cell.frame));
+                    let diff = directory.join(format!("{}-diff.png", cell.frame));
+                    let saved = image
+                        .save(&actual)
+                        .and_then(|_| diff_image(&reference, image).save(&diff));
+                    if let Err(e) = saved {
+                        eprintln!("Could not write the diff images: {}", e);
+                    }
+                }
+            }
+        }
+        success
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, delta_e, diff_image, lab};
+    use nannou::image::{Rgba, RgbaImage};
+
+    #[test]
+    fn lab_of_reference_colors() {
+        let white = lab(&Rgba([255, 255, 255, 255]));
+        assert!((white[0] - 100.).abs() < 0.1 && white[1].abs() < 0.1 && white[2].abs() < 0.1);
+        assert!(lab(&Rgba([0, 0, 0, 255]))[0].abs() < 0.01);
+        // Pure red is about (53.2, 80.1, 67.2).
+        let red = lab(&Rgba([255, 0, 0, 255]));
+        assert!((red[0] - 53.2).abs() < 0.5 && (red[1] - 80.1).abs() < 0.5);
+        assert!(delta_e(&Rgba([100, 100, 100, 255]), &Rgba([101, 100, 100, 255])) < 1.);
+    }
+
+    #[test]
+    fn tolerate_small_differences() {
+        let reference = RgbaImage::from_pixel(100, 100, Rgba([40, 80, 120, 255]));
+        let mut actual = reference.clone();
+        // Slightly different colors everywhere and one very different pixel.
+        for pixel in actual.pixels_mut() {
+            pixel[0] += 1;
+        }
+        actual.put_pixel(3, 4, Rgba([255, 255, 255, 255]));
+        let comparison = compare(&reference, &actual).unwrap();
+        assert_eq!(comparison.differing, 0.0001);
+        assert!(comparison.passes());
+
+        // A whole row is a real change.
+        for x in 0..100 {
+            actual.put_pixel(x, 0, Rgba([255, 255, 255, 255]));
+        }
+        assert!(!compare(&reference, &actual).unwrap().passes());
+        let diff = diff_image(&reference, &actual);
+        assert_eq!(*diff.get_pixel(3, 4), Rgba([255, 0, 0, 255]));
+        assert_ne!(*diff.get_pixel(50, 50), Rgba([255, 0, 0, 255]));
+
+        assert_eq!(compare(&reference, &RgbaImage::new(10, 10)), None);
+    }
+}
@@ -0,0 +1,145 @@
+//! The time the GPU takes to render the frames, shown in the overlay.
+//!
+//! When the adapter supports `TIMESTAMP_QUERY`, timestamps are written before
+//! and after the commands of a frame and read back without waiting for the
+//! GPU: the read is checked at the next updates, so the time shown is the one
+//! of a frame or two before. Frames rendered while the previous read is still
+//! pending are not timed. Without the feature, nothing is measured.
+use nannou::wgpu;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    time::Duration,
+};
+
+/// The pending read of the timestamps.
+type Mapping = Pin<Box<dyn Future<Output = Result<(), wgpu::BufferAsyncError>> + Send>>;
+
+/// Wakes nothing: the read is polled at each update instead.
+struct NoopWake;
+
+impl Wake for NoopWake {
+    fn wake(self: Arc<Self>) {}
+}
+
+/// The duration between the timestamps `start` and `end`, of `period`
+/// nanoseconds per tick.
+fn elapsed(start: u64, end: u64, period: f32) -> Duration {
+    Duration::from_nanos((end.saturating_sub(start) as f64 * period as f64).round() as u64)
+}
+
+pub(crate) struct GpuTimer {
+    queries: wgpu::QuerySet,
+    /// The buffer the timestamps are resolved to and read from.
+    buffer: wgpu::Buffer,
+    /// The nanoseconds of a timestamp tick.
+    period: f32,
+    /// Whether the timestamps of the frame being encoded are written.
+    timing: bool,
+    mapping: Option<Mapping>,
+    /// The duration of the last frame read back.
+    last: Option<Duration>,
+}
+
+impl GpuTimer {
+    /// A timer, if `device` was created with timestamp queries.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<GpuTimer> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let queries = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("frame timestamps"),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2,
+        });
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("frame timestamps"),
+            size: 2 * std::mem::size_of::<u64>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Some(GpuTimer {
+            queries,
+            buffer,
+            period: queue.get_timestamp_period(),
+            timing: false,
+            mapping: None,
+            last: None,
+        })
+    }
+
+    /// The duration of a recent frame, if one was read back.
+    pub fn last(&self) -> Option<Duration> {
+        self.last
+    }
+
+    /// Write the timestamp of the start of a frame, unless the previous ones
+    /// are still being read.
+    pub fn begin(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        self.timing = self.mapping.is_none();
+        if self.timing {
+            encoder.write_timestamp(&self.queries, 0);
+        }
+    }
+
+    /// Write the timestamp of the end of the frame, and resolve both to the
+    /// buffer.
+    pub fn end(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        if self.timing {
+            encoder.write_timestamp(&self.queries, 1);
+            encoder.resolve_query_set(&self.queries, 0..2, &self.buffer, 0);
+        }
+    }
+
+    /// Start reading the timestamps, once the frame was submitted.
+    pub fn submitted(&mut self) {
+        if self.timing {
+            self.timing = false;
+            self.mapping = Some(Box::pin(
+                self.buffer.slice(..).map_async(wgpu::MapMode::Read),
+            ));
+        }
+    }
+
+    /// Read the timestamps if the GPU is done with them, without waiting.
+    pub fn poll(&mut self, device: &wgpu::Device) {
+        let mapping = match &mut self.mapping {
+            Some(mapping) => mapping,
+            None => return,
+        };
+        device.poll(wgpu::Maintain::Poll);
+        let waker = Waker::from(Arc::new(NoopWake));
+        match mapping.as_mut().poll(&mut Context::from_waker(&waker)) {
+            Poll::Pending => return,
+            Poll::Ready(Ok(())) => {
+                let range = self.buffer.slice(..).get_mapped_range();
+                let timestamps: Vec<u64> = range
+                    .chunks_exact(8)
+                    .map(|bytes| u64::from_ne_bytes(bytes.try_into().unwrap()))
+                    .collect();
+                drop(range);
+                self.buffer.unmap();
+                self.last = Some(elapsed(timestamps[0], timestamps[1], self.period));
+            }
+            Poll::Ready(Err(e)) => eprintln!("Could not read the GPU time: {:?}", e),
+        }
+        self.mapping = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::elapsed;
+    use std::time::Duration;
+
+    #[test]
+    fn ticks_to_durations() {
+        assert_eq!(elapsed(1000, 3000, 1.), Duration::from_micros(2));
+        // Some GPUs count ticks of several nanoseconds.
+        assert_eq!(elapsed(0, 1_000_000, 83.333), Duration::from_micros(83_333));
+        // Timestamps written out of order don't underflow.
+        assert_eq!(elapsed(3000, 1000, 1.), Duration::ZERO);
+    }
+}
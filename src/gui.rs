@@ -0,0 +1,217 @@
+//! A side panel to control the artwork from the preview window.
+//!
+//! The panel lists the registered parameters, the [`Options`], the seed and
+//! its bookmarks, the playback controls and the recording state. It is hidden
+//! by default and toggled with F2. The panel is drawn on the window frame only,
+//! so it never ends up in the recorded frames.
+use crate::{apply_options, apply_preset, set_recording, BaseModel, Options};
+use nannou::{prelude::*, winit::event::WindowEvent};
+use nannou_egui::{egui, Egui};
+
+pub(crate) struct Gui {
+    egui: Egui,
+    pub visible: bool,
+}
+
+/// What the user asked for in the panel, applied once the panel is drawn.
+enum Action {
+    TogglePlaying,
+    Step(i64),
+    Jump(u32),
+    Faster,
+    Slower,
+    ToggleScrubbing,
+    NewSeed,
+    SetSeed(i32),
+    BookmarkSeed,
+    ToggleRecording,
+    ApplyOptions(Options),
+    LoadPreset(String),
+}
+
+impl Gui {
+    pub fn new(window: &Window) -> Gui {
+        Gui {
+            egui: Egui::from_window(window),
+            visible: false,
+        }
+    }
+
+    pub fn handle_raw_event(&mut self, event: &WindowEvent) {
+        self.egui.handle_raw_event(event);
+    }
+
+    /// Whether the panel should receive the key presses instead of the
+    /// artwork, e.g. while typing a value.
+    pub fn wants_keyboard(&self) -> bool {
+        self.visible && self.egui.ctx().wants_keyboard_input()
+    }
+
+    /// Whether the mouse is used by the panel, in which case it should not
+    /// scrub through the animation.
+    pub fn wants_pointer(&self) -> bool {
+        self.visible && self.egui.ctx().wants_pointer_input()
+    }
+
+    pub fn draw_to_frame(&self, frame: &Frame) {
+        if self.visible {
+            self.egui.draw_to_frame(frame).unwrap();
+        }
+    }
+}
+
+pub(crate) fn update(app: &App, base_model: &mut BaseModel, update: &Update, n_frames: u32) {
+    if !base_model.gui.visible {
+        return;
+    }
+    base_model.gui.egui.set_elapsed_time(update.since_start);
+    let mut actions = vec![];
+    {
+        let ctx = base_model.gui.egui.begin_frame();
+        egui::SidePanel::left("artwork controls")
+            .default_width(180.)
+            .show(&ctx, |ui| {
+                ui.heading("Artwork");
+                let mut frame = base_model.transport.frame(n_frames);
+                ui.horizontal(|ui| {
+                    ui.label("Frame");
+                    let drag = egui::DragValue::new(&mut frame).clamp_range(0..=n_frames - 1);
+                    if ui.add(drag).changed() {
+                        actions.push(Action::Jump(frame));
+                    }
+                    ui.label(format!("/ {}", n_frames));
+                });
+                let transport = &mut base_model.transport;
+                ui.horizontal(|ui| {
+                    if ui.small_button("<").clicked() {
+                        actions.push(Action::Step(-1));
+                    }
+                    let play_label = if transport.playing { "Pause" } else { "Play" };
+                    if ui.button(play_label).clicked() {
+                        actions.push(Action::TogglePlaying);
+                    }
+                    if ui.small_button(">").clicked() {
+                        actions.push(Action::Step(1));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.small_button("-").clicked() {
+                        actions.push(Action::Slower);
+                    }
+                    ui.label(format!("Speed x{}", transport.speed));
+                    if ui.small_button("+").clicked() {
+                        actions.push(Action::Faster);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut transport.looping, "Loop");
+                    let mut scrubbing = transport.scrubbing;
+                    if ui.checkbox(&mut scrubbing, "Mouse scrub").changed() {
+                        actions.push(Action::ToggleScrubbing);
+                    }
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Seed");
+                    ui.add(egui::DragValue::new(&mut base_model.seed));
+                    if ui.button("New").clicked() {
+                        actions.push(Action::NewSeed);
+                    }
+                    if ui.button("Bookmark").clicked() {
+                        actions.push(Action::BookmarkSeed);
+                    }
+                });
+                let record_label = if base_model.recording {
+                    format!("Stop recording ({})", base_model.current_frame)
+                } else {
+                    "Start recording".to_string()
+                };
+                if ui.button(record_label).clicked() {
+                    actions.push(Action::ToggleRecording);
+                }
+
+                if !base_model.params.is_empty() {
+                    ui.separator();
+                    ui.heading("Parameters");
+                    for param in base_model.params.iter_mut() {
+                        ui.add(
+                            egui::Slider::new(&mut param.value, param.min..=param.max)
+                                .text(&param.name),
+                        );
+                    }
+                }
+
+                ui.separator();
+                ui.heading("Options");
+                let mut options = base_model.options.clone();
+                let mut changed = false;
+                changed |= ui
+                    .add(egui::Slider::new(&mut options.chroma, 0.0..=1.0).text("chroma"))
+                    .changed();
+                changed |= ui
+                    .add(egui::Slider::new(&mut options.sample_per_frame, 1..=32).text("samples"))
+                    .changed();
+                changed |= ui
+                    .add(egui::Slider::new(&mut options.shutter_angle, 0.0..=1.0).text("shutter"))
+                    .changed();
+                changed |= ui
+                    .add(egui::Slider::new(&mut options.noise_amount, 0.0..=1.0).text("noise"))
+                    .changed();
+                if changed {
+                    actions.push(Action::ApplyOptions(options));
+                }
+
+                ui.separator();
+                ui.heading("Presets");
+                let presets = &base_model.presets;
+                egui::ComboBox::from_id_source("presets")
+                    .selected_text(presets.current_name().unwrap_or("-"))
+                    .show_ui(ui, |ui| {
+                        for name in presets.names() {
+                            let selected = presets.current_name() == Some(name.as_str());
+                            if ui.selectable_label(selected, name).clicked() {
+                                actions.push(Action::LoadPreset(name.clone()));
+                            }
+                        }
+                    });
+
+                if base_model.bookmarks.iter().next().is_some() {
+                    ui.separator();
+                    ui.heading("Bookmarks");
+                    ui.horizontal_wrapped(|ui| {
+                        for bookmark in base_model.bookmarks.iter() {
+                            let selected = bookmark.seed == base_model.seed;
+                            let label = bookmark.seed.to_string();
+                            if ui.selectable_label(selected, label).clicked() {
+                                actions.push(Action::SetSeed(bookmark.seed));
+                            }
+                        }
+                    });
+                }
+            });
+    }
+
+    for action in actions {
+        let transport = &mut base_model.transport;
+        match action {
+            Action::TogglePlaying => transport.toggle_playing(n_frames),
+            Action::Step(frames) => transport.step(frames, n_frames),
+            Action::Jump(frame) => transport.jump(frame, n_frames),
+            Action::Faster => transport.faster(),
+            Action::Slower => transport.slower(),
+            Action::ToggleScrubbing => transport.toggle_scrubbing(),
+            Action::NewSeed => base_model.seed = random(),
+            Action::SetSeed(seed) => base_model.seed = seed,
+            Action::BookmarkSeed => base_model.bookmark_requested = true,
+            Action::ToggleRecording => {
+                let recording = !base_model.recording;
+                set_recording(base_model, recording);
+            }
+            Action::ApplyOptions(options) => apply_options(app, base_model, options),
+            Action::LoadPreset(name) => match base_model.presets.load(&name) {
+                Ok(preset) => apply_preset(app, base_model, preset),
+                Err(e) => eprintln!("Could not load preset {}: {}", name, e),
+            },
+        }
+    }
+}
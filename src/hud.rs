@@ -0,0 +1,132 @@
+//! An overlay with the state of the preview, toggled with F1.
+//!
+//! Like the control panel, the overlay is drawn on the window frame only, so it
+//! never ends up in the recorded frames.
+use crate::BaseModel;
+use nannou::prelude::*;
+use std::time::Duration;
+
+const FONT_SIZE: u32 = 14;
+const LINE_HEIGHT: f32 = 18.;
+const WIDTH: f32 = 240.;
+const MARGIN: f32 = 10.;
+
+pub(crate) struct Hud {
+    pub visible: bool,
+    /// The frame rendered at the last update, and the number of frames of the
+    /// animation.
+    pub frame: u32,
+    pub n_frames: u32,
+    /// The time the GPU took to render a recent frame, if it supports
+    /// timestamp queries. See [`gpu_timer`](crate::gpu_timer).
+    pub gpu_time: Option<Duration>,
+}
+
+impl Hud {
+    pub fn new() -> Hud {
+        Hud {
+            visible: false,
+            frame: 0,
+            n_frames: 0,
+            gpu_time: None,
+        }
+    }
+}
+
+/// Format a frame as minutes, seconds and frames, e.g. `01:04:12`.
+fn timecode(frame: u32, fps: u32) -> String {
+    let seconds = frame / fps;
+    format!("{:02}:{:02}:{:02}", seconds / 60, seconds % 60, frame % fps)
+}
+
+/// The line of the GPU time of a frame, or n/a where it isn't measured.
+fn gpu_time(time: Option<Duration>) -> String {
+    match time {
+        Some(time) => format!("GPU {:.1} ms", time.as_secs_f64() * 1000.),
+        None => "GPU n/a".to_string(),
+    }
+}
+
+/// The lines of text of the overlay.
+fn lines(app: &App, base_model: &BaseModel) -> Vec<String> {
+    let hud = &base_model.hud;
+    let transport = &base_model.transport;
+    let mut lines = vec![
+        format!(
+            "Frame {} / {}  {}",
+            hud.frame,
+            hud.n_frames,
+            timecode(hud.frame, crate::FPS)
+        ),
+        format!("Time {:.4}", hud.frame as f64 / hud.n_frames.max(1) as f64),
+        format!("Seed {}", base_model.seed),
+        format!("FPS {:.1}", app.fps()),
+        format!(
+            "Samples {}  shutter {:.2}",
+            base_model.options.sample_per_frame, base_model.options.shutter_angle
+        ),
+        gpu_time(hud.gpu_time),
+    ];
+    if base_model.recording {
+        lines.push(format!(
+            "Recording {:.0}% ({} / {})",
+            100. * base_model.current_frame as f32 / hud.n_frames.max(1) as f32,
+            base_model.current_frame,
+            hud.n_frames
+        ));
+    } else {
+        let state = if transport.playing {
+            "Playing"
+        } else {
+            "Paused"
+        };
+        let looping = if transport.looping { "loop" } else { "once" };
+        lines.push(format!("{} x{} {}", state, transport.speed, looping));
+    }
+    lines
+}
+
+/// Draw the overlay in the top right corner of the window.
+pub(crate) fn draw_to_frame(app: &App, base_model: &BaseModel, frame: &Frame) {
+    if !base_model.hud.visible {
+        return;
+    }
+    let lines = lines(app, base_model);
+    let window = app.window_rect();
+    let height = LINE_HEIGHT * lines.len() as f32 + 2. * MARGIN;
+    let rect = Rect::from_w_h(WIDTH, height).top_right_of(window.pad(MARGIN));
+
+    let draw = app.draw();
+    draw.rect()
+        .xy(rect.xy())
+        .wh(rect.wh())
+        .color(srgba(0., 0., 0., 0.6));
+    draw.text(&lines.join("\n"))
+        .xy(rect.xy())
+        .wh(rect.pad(MARGIN).wh())
+        .font_size(FONT_SIZE)
+        .line_spacing(LINE_HEIGHT - FONT_SIZE as f32)
+        .left_justify()
+        .align_text_top()
+        .color(WHITE);
+    draw.to_frame(app, frame).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gpu_time, timecode};
+    use std::time::Duration;
+
+    #[test]
+    fn format_timecode() {
+        assert_eq!(timecode(0, 60), "00:00:00");
+        assert_eq!(timecode(59, 60), "00:00:59");
+        assert_eq!(timecode(60 * 64 + 12, 60), "01:04:12");
+    }
+
+    #[test]
+    fn format_gpu_time() {
+        assert_eq!(gpu_time(Some(Duration::from_micros(16_640))), "GPU 16.6 ms");
+        assert_eq!(gpu_time(None), "GPU n/a");
+    }
+}
@@ -0,0 +1,624 @@
+//! Named layers, each drawn with its own `Draw` and composited over the base
+//! drawing with a blend mode, an opacity and color effects.
+//!
+//! An artwork declares its layers, from the bottom to the top, with
+//! [`Artwork::layers`](crate::Artwork::layers). `base.draw` stays the bottom of
+//! the stack, and each layer is blended over the composite of the ones below:
+//!
+//! ```ignore
+//! fn layers() -> Vec<Layer> {
+//!     vec![
+//!         Layer::new("shadows").blend(Blend::Multiply),
+//!         Layer::new("glow").blend(Blend::Screen).opacity(0.8),
+//!     ]
+//! }
+//!
+//! fn draw_at_time(&mut self, time: f64) {
+//!     let draw = &self.base.draw;
+//!     draw.reset();
+//!     draw.background().color(BLACK);
+//!     let glow = self.base.layers.draw("glow");
+//!     glow.ellipse().radius(200.).color(ORANGE);
+//!     // The blend mode, opacity and effects can change at any time.
+//!     self.base.layers.layer_mut("glow").opacity = time as f32;
+//! }
+//! ```
+//!
+//! The layers start each motion blur sample transparent, and their drawing is
+//! reset once rendered. The composite replaces the base drawing, so the post
+//! effects of the shader and the feedback texture apply to all the layers.
+//!
+//! # Masks
+//!
+//! The drawing of a mask layer is not composited: any shape drawn in it becomes
+//! a mask, from its alpha or as a stencil. Another layer can be shown only
+//! inside or outside of the mask, and primitives can be clipped one by one by
+//! drawing them with [`draw_inside`](Layers::draw_inside) or
+//! [`draw_outside`](Layers::draw_outside). Those are composited at the place of
+//! the mask layer in the stack, with its blend mode, opacity and effects.
+//!
+//! ```ignore
+//! fn layers() -> Vec<Layer> {
+//!     vec![
+//!         Layer::new("hole").as_mask(MaskKind::Stencil),
+//!         Layer::new("tiles").mask("hole", Clip::Outside),
+//!     ]
+//! }
+//!
+//! fn draw_at_time(&mut self, time: f64) {
+//!     let layers = &self.base.layers;
+//!     layers.draw("hole").ellipse().radius(300.);
+//!     // The tiles are drawn around the hole, the stars only inside of it.
+//!     layers.draw("tiles").rect().w_h(100., 100.);
+//!     layers.draw_inside("hole").ellipse().radius(4.).color(WHITE);
+//! }
+//! ```
+//!
+//! # Memory
+//!
+//! Each layer is drawn to a texture of the size, sample count and format of
+//! the animation, a multisampled `Rgba16Float` texture of about 150 MB at 2160
+//! pixels. A mask layer takes three of them, for the mask and the drawings
+//! inside and outside of it, and any layers add two more for the composites.
+//! The `hole` example, with a layer and a mask, takes six, about 900 MB.
+use crate::{uniforms_as_bytes, Vertex, VERTICES};
+use nannou::{
+    prelude::*,
+    wgpu::{self, TextureViewDimension},
+};
+
+/// How a layer is combined with the layers below.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Blend {
+    Normal,
+    Multiply,
+    Screen,
+    Add,
+    Overlay,
+    Difference,
+}
+
+/// The side of a mask a drawing is shown on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Clip {
+    Inside,
+    Outside,
+}
+
+/// How the drawing of a mask layer becomes a mask.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaskKind {
+    /// The alpha of the drawing, keeping antialiased edges and translucent
+    /// shapes.
+    Alpha,
+    /// Wherever the drawing covers at least half of a pixel.
+    Stencil,
+}
+
+/// Color adjustments of a layer, applied before it is blended.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Effects {
+    /// Added to the colors, 0 by default.
+    pub brightness: f32,
+    /// Scales the colors around mid-grey, 1 by default.
+    pub contrast: f32,
+    /// 0 turns the layer grey, 1 by default.
+    pub saturation: f32,
+}
+
+impl Default for Effects {
+    fn default() -> Effects {
+        Effects {
+            brightness: 0.,
+            contrast: 1.,
+            saturation: 1.,
+        }
+    }
+}
+
+/// A layer of the artwork and how it is composited.
+#[derive(Clone, Debug)]
+pub struct Layer {
+    pub name: String,
+    pub blend: Blend,
+    /// Between 0 and 1.
+    pub opacity: f32,
+    pub effects: Effects,
+    /// Makes the layer a mask, drawn but not composited. The masks are bound
+    /// to the layers once, so they can't change while drawing.
+    mask_kind: Option<MaskKind>,
+    /// The mask layer the layer is clipped by, and on which side.
+    masked_by: Option<(String, Clip)>,
+}
+
+impl Layer {
+    pub fn new(name: &str) -> Layer {
+        Layer {
+            name: name.to_string(),
+            blend: Blend::Normal,
+            opacity: 1.,
+            effects: Effects::default(),
+            mask_kind: None,
+            masked_by: None,
+        }
+    }
+
+    pub fn blend(mut self, blend: Blend) -> Layer {
+        self.blend = blend;
+        self
+    }
+
+    pub fn opacity(mut self, opacity: f32) -> Layer {
+        self.opacity = opacity;
+        self
+    }
+
+    pub fn brightness(mut self, brightness: f32) -> Layer {
+        self.effects.brightness = brightness;
+        self
+    }
+
+    pub fn contrast(mut self, contrast: f32) -> Layer {
+        self.effects.contrast = contrast;
+        self
+    }
+
+    pub fn saturation(mut self, saturation: f32) -> Layer {
+        self.effects.saturation = saturation;
+        self
+    }
+
+    /// Make the layer a mask for other layers and for clipped primitives.
+    pub fn as_mask(mut self, kind: MaskKind) -> Layer {
+        self.mask_kind = Some(kind);
+        self
+    }
+
+    /// Show the layer only inside or outside of the mask layer `mask`.
+    pub fn mask(mut self, mask: &str, clip: Clip) -> Layer {
+        self.masked_by = Some((mask.to_string(), clip));
+        self
+    }
+
+    fn uniforms(&self, mask: Option<(Clip, MaskKind)>) -> LayerUniforms {
+        LayerUniforms {
+            blend: match self.blend {
+                Blend::Normal => 0,
+                Blend::Multiply => 1,
+                Blend::Screen => 2,
+                Blend::Add => 3,
+                Blend::Overlay => 4,
+                Blend::Difference => 5,
+            },
+            opacity: self.opacity.clamp(0., 1.),
+            brightness: self.effects.brightness,
+            contrast: self.effects.contrast,
+            saturation: self.effects.saturation,
+            clip: match mask {
+                None => 0,
+                Some((Clip::Inside, _)) => 1,
+                Some((Clip::Outside, _)) => 2,
+            },
+            stencil: matches!(mask, Some((_, MaskKind::Stencil))) as i32,
+        }
+    }
+}
+
+/// The uniform values of the composite shader.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct LayerUniforms {
+    blend: i32,
+    opacity: f32,
+    brightness: f32,
+    contrast: f32,
+    saturation: f32,
+    clip: i32,
+    stencil: i32,
+}
+
+/// A drawing composited in the stack: a layer, or the primitives clipped by a
+/// mask layer.
+#[derive(Debug, PartialEq)]
+struct Slot {
+    /// The index of the layer giving the blend mode, opacity and effects.
+    layer: usize,
+    /// The index of the mask layer, and the side of the mask shown.
+    mask: Option<(usize, Clip)>,
+}
+
+/// The drawings composited, from the bottom to the top. A mask layer is
+/// replaced by the primitives drawn inside and outside of it.
+fn slots(layers: &[Layer]) -> Vec<Slot> {
+    let mut slots = vec![];
+    for (i, layer) in layers.iter().enumerate() {
+        if layer.mask_kind.is_some() {
+            if layer.masked_by.is_some() {
+                panic!("The mask layer {} can't be masked", layer.name);
+            }
+            for clip in [Clip::Inside, Clip::Outside] {
+                slots.push(Slot {
+                    layer: i,
+                    mask: Some((i, clip)),
+                });
+            }
+            continue;
+        }
+        let mask = layer.masked_by.as_ref().map(|(name, clip)| {
+            let mask = layers
+                .iter()
+                .position(|l| &l.name == name && l.mask_kind.is_some())
+                .unwrap_or_else(|| {
+                    panic!(
+                        "The layer {} is masked by {}, which is not a mask layer",
+                        layer.name, name
+                    )
+                });
+            (mask, *clip)
+        });
+        slots.push(Slot { layer: i, mask });
+    }
+    slots
+}
+
+/// A drawing rendered to a texture of its own.
+struct Canvas {
+    draw: Draw,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+}
+
+impl Canvas {
+    fn new(device: &wgpu::Device, output: &wgpu::Texture) -> Canvas {
+        let texture = layer_texture(device, output);
+        Canvas {
+            draw: Draw::new(),
+            view: texture.view().build(),
+            texture,
+        }
+    }
+
+    /// Clear the texture and render the drawing to it, then reset the drawing.
+    fn encode(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        renderer: &mut nannou::draw::Renderer,
+    ) {
+        // Drawings without a background would keep the previous sample.
+        wgpu::RenderPassBuilder::new()
+            .color_attachment(&self.view, |color| color)
+            .begin(encoder);
+        renderer.render_to_texture(device, encoder, &self.draw, &self.texture);
+        self.draw.reset();
+    }
+}
+
+/// A drawing composited in the stack, with the GPU resources to composite it.
+struct Target {
+    slot: Slot,
+    canvas: Canvas,
+    uniforms: wgpu::Buffer,
+    /// Binds the composite below, the drawing, the mask and the uniforms.
+    bind_group: wgpu::BindGroup,
+}
+
+/// The textures and pipeline shared by the layers.
+struct Compositor {
+    /// The layers are composited in these textures alternately, starting with
+    /// the base drawing in the first one.
+    composites: [wgpu::Texture; 2],
+    composite_views: [wgpu::TextureView; 2],
+    /// The texture drawn to without layers, which receives the last composite.
+    output_view: wgpu::TextureView,
+    pipeline: wgpu::RenderPipeline,
+}
+
+/// The layers of an artwork.
+pub struct Layers {
+    layers: Vec<Layer>,
+    /// The drawings of the mask layers, in the same order as `layers`.
+    masks: Vec<Option<Canvas>>,
+    targets: Vec<Target>,
+    compositor: Option<Compositor>,
+}
+
+fn layer_texture(device: &wgpu::Device, output: &wgpu::Texture) -> wgpu::Texture {
+    wgpu::TextureBuilder::new()
+        .size(output.size())
+        .usage(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+        .sample_count(output.sample_count())
+        .format(output.format())
+        .build(device)
+}
+
+impl Layers {
+    /// Create the `layers` composited into `output`, the texture drawn to.
+    pub(crate) fn new(device: &wgpu::Device, output: &wgpu::Texture, layers: Vec<Layer>) -> Layers {
+        if layers.is_empty() {
+            return Layers {
+                layers,
+                masks: vec![],
+                targets: vec![],
+                compositor: None,
+            };
+        }
+        let composites = [layer_texture(device, output), layer_texture(device, output)];
+        let composite_views = [composites[0].view().build(), composites[1].view().build()];
+
+        // The composite below, the drawing and the mask.
+        let bind_group_layout = wgpu::BindGroupLayoutBuilder::new()
+            .texture(
+                wgpu::ShaderStages::FRAGMENT,
+                true,
+                TextureViewDimension::D2,
+                output.sample_type(),
+            )
+            .texture(
+                wgpu::ShaderStages::FRAGMENT,
+                true,
+                TextureViewDimension::D2,
+                output.sample_type(),
+            )
+            .texture(
+                wgpu::ShaderStages::FRAGMENT,
+                true,
+                TextureViewDimension::D2,
+                output.sample_type(),
+            )
+            .uniform_buffer(wgpu::ShaderStages::FRAGMENT, false)
+            .build(device);
+        let desc = wgpu::PipelineLayoutDescriptor {
+            label: Some("The layer pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        };
+        let pipeline_layout = device.create_pipeline_layout(&desc);
+        let vs_mod = device.create_shader_module(&wgpu::include_wgsl!("shaders/vs.wgsl"));
+        let fs_mod = device.create_shader_module(&wgpu::include_wgsl!("shaders/composite.wgsl"));
+        // The shader blends itself, so its output replaces the target.
+        let pipeline = wgpu::RenderPipelineBuilder::from_layout(&pipeline_layout, &vs_mod)
+            .fragment_shader(&fs_mod)
+            .color_format(output.format())
+            .color_blend(wgpu::BlendComponent::REPLACE)
+            .alpha_blend(wgpu::BlendComponent::REPLACE)
+            .add_vertex_buffer::<Vertex>(&wgpu::vertex_attr_array![0 => Float32x2])
+            .sample_count(output.sample_count())
+            .primitive_topology(wgpu::PrimitiveTopology::TriangleStrip)
+            .build(device);
+
+        let masks: Vec<Option<Canvas>> = layers
+            .iter()
+            .map(|layer| layer.mask_kind.map(|_| Canvas::new(device, output)))
+            .collect();
+        let targets = slots(&layers)
+            .into_iter()
+            .enumerate()
+            .map(|(i, slot)| {
+                let canvas = Canvas::new(device, output);
+                let uniforms = device.create_buffer_init(&BufferInitDescriptor {
+                    label: None,
+                    contents: uniforms_as_bytes(&layers[slot.layer].uniforms(None)),
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                });
+                // Without a mask, the drawing is bound in its place and ignored.
+                let mask_view = match slot.mask {
+                    Some((mask, _)) => &masks[mask].as_ref().unwrap().view,
+                    None => &canvas.view,
+                };
+                let bind_group = wgpu::BindGroupBuilder::new()
+                    .texture_view(&composite_views[i % 2])
+                    .texture_view(&canvas.view)
+                    .texture_view(mask_view)
+                    .buffer::<LayerUniforms>(&uniforms, 0..1)
+                    .build(device, &bind_group_layout);
+                Target {
+                    slot,
+                    canvas,
+                    uniforms,
+                    bind_group,
+                }
+            })
+            .collect();
+        Layers {
+            layers,
+            masks,
+            targets,
+            compositor: Some(Compositor {
+                composites,
+                composite_views,
+                output_view: output.view().build(),
+                pipeline,
+            }),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    fn index(&self, name: &str) -> usize {
+        self.layers
+            .iter()
+            .position(|l| l.name == name)
+            .unwrap_or_else(|| panic!("No layer named {} in Artwork::layers", name))
+    }
+
+    /// The `Draw` of the layer `name`. For a mask layer, the shapes drawn make
+    /// the mask.
+    pub fn draw(&self, name: &str) -> &Draw {
+        let index = self.index(name);
+        if let Some(mask) = &self.masks[index] {
+            return &mask.draw;
+        }
+        let target = self.targets.iter().find(|t| t.slot.layer == index);
+        &target.unwrap().canvas.draw
+    }
+
+    fn clipped(&self, name: &str, clip: Clip) -> &Draw {
+        let index = self.index(name);
+        if self.layers[index].mask_kind.is_none() {
+            panic!("The layer {} is not a mask layer", name);
+        }
+        let target = self
+            .targets
+            .iter()
+            .find(|t| t.slot.layer == index && t.slot.mask == Some((index, clip)));
+        &target.unwrap().canvas.draw
+    }
+
+    /// A `Draw` whose primitives are only shown inside of the mask layer
+    /// `name`.
+    pub fn draw_inside(&self, name: &str) -> &Draw {
+        self.clipped(name, Clip::Inside)
+    }
+
+    /// A `Draw` whose primitives are only shown outside of the mask layer
+    /// `name`.
+    pub fn draw_outside(&self, name: &str) -> &Draw {
+        self.clipped(name, Clip::Outside)
+    }
+
+    /// The layer `name`, to change how it is composited.
+    pub fn layer_mut(&mut self, name: &str) -> &mut Layer {
+        let index = self.index(name);
+        &mut self.layers[index]
+    }
+
+    /// Reset the drawings of all the layers and masks.
+    pub(crate) fn reset(&self) {
+        let masks = self.masks.iter().flatten();
+        for canvas in masks.chain(self.targets.iter().map(|t| &t.canvas)) {
+            canvas.draw.reset();
+        }
+    }
+
+    /// The texture the base drawing is rendered to instead of the output, if
+    /// there are layers to composite over it.
+    pub(crate) fn base_texture(&self) -> Option<&wgpu::Texture> {
+        self.compositor.as_ref().map(|c| &c.composites[0])
+    }
+
+    /// Render the masks and the layers, and composite the layers over the base
+    /// drawing into the output texture, as part of the commands recorded in
+    /// `encoder`.
+    pub(crate) fn encode(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        renderer: &mut nannou::draw::Renderer,
+        vertex_buffer: &wgpu::Buffer,
+    ) {
+        let compositor = match &self.compositor {
+            Some(compositor) => compositor,
+            None => return,
+        };
+        for mask in self.masks.iter().flatten() {
+            mask.encode(device, encoder, renderer);
+        }
+        for (i, target) in self.targets.iter().enumerate() {
+            target.canvas.encode(device, encoder, renderer);
+
+            // The uniforms are copied within the encoder, so that each sample
+            // of the frame uses its own values.
+            let mask = target.slot.mask.map(|(mask, clip)| {
+                let kind = self.layers[mask].mask_kind.unwrap();
+                (clip, kind)
+            });
+            let uniforms = self.layers[target.slot.layer].uniforms(mask);
+            let staging = device.create_buffer_init(&BufferInitDescriptor {
+                label: None,
+                contents: uniforms_as_bytes(&uniforms),
+                usage: wgpu::BufferUsages::COPY_SRC,
+            });
+            let size = std::mem::size_of::<LayerUniforms>() as u64;
+            encoder.copy_buffer_to_buffer(&staging, 0, &target.uniforms, 0, size);
+
+            let output = if i + 1 == self.targets.len() {
+                &compositor.output_view
+            } else {
+                &compositor.composite_views[(i + 1) % 2]
+            };
+            let mut render_pass = wgpu::RenderPassBuilder::new()
+                .color_attachment(output, |color| color)
+                .begin(encoder);
+            render_pass.set_bind_group(0, &target.bind_group, &[]);
+            render_pass.set_pipeline(&compositor.pipeline);
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            render_pass.draw(0..VERTICES.len() as u32, 0..1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{slots, Blend, Clip, Effects, Layer, LayerUniforms, MaskKind, Slot};
+
+    #[test]
+    fn layer_uniforms() {
+        let layer = Layer::new("glow");
+        assert_eq!(layer.blend, Blend::Normal);
+        assert_eq!(layer.effects, Effects::default());
+        let layer = layer
+            .blend(Blend::Overlay)
+            .opacity(1.5)
+            .brightness(0.1)
+            .saturation(0.);
+        assert_eq!(
+            layer.uniforms(None),
+            LayerUniforms {
+                blend: 4,
+                opacity: 1.,
+                brightness: 0.1,
+                contrast: 1.,
+                saturation: 0.,
+                clip: 0,
+                stencil: 0,
+            }
+        );
+        let layer = Layer::new("a").blend(Blend::Difference);
+        let uniforms = layer.uniforms(Some((Clip::Outside, MaskKind::Stencil)));
+        assert_eq!((uniforms.blend, uniforms.clip, uniforms.stencil), (5, 2, 1));
+        let uniforms = layer.uniforms(Some((Clip::Inside, MaskKind::Alpha)));
+        assert_eq!((uniforms.clip, uniforms.stencil), (1, 0));
+    }
+
+    #[test]
+    fn mask_layers_are_replaced_by_clipped_drawings() {
+        let layers = [
+            Layer::new("sky"),
+            Layer::new("tiles").mask("hole", Clip::Outside),
+            Layer::new("hole").as_mask(MaskKind::Alpha),
+        ];
+        assert_eq!(
+            slots(&layers),
+            vec![
+                Slot {
+                    layer: 0,
+                    mask: None
+                },
+                Slot {
+                    layer: 1,
+                    mask: Some((2, Clip::Outside))
+                },
+                Slot {
+                    layer: 2,
+                    mask: Some((2, Clip::Inside))
+                },
+                Slot {
+                    layer: 2,
+                    mask: Some((2, Clip::Outside))
+                },
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "masked by sky, which is not a mask layer")]
+    fn mask_must_be_a_mask_layer() {
+        slots(&[
+            Layer::new("sky"),
+            Layer::new("tiles").mask("sky", Clip::Inside),
+        ]);
+    }
+}
@@ -13,19 +13,62 @@
 /// By default, you animation is not being recorded, but you can start a
 /// recording by pressing R.
 pub mod artwork;
+pub mod assets;
+pub mod audio;
+mod batch;
+mod contact_sheet;
+pub mod feedback;
+pub mod footage;
+mod frame_writer;
+mod golden;
+mod gpu_timer;
+mod gui;
+mod hud;
+pub mod layers;
+mod loop_check;
+pub mod midi;
+pub mod osc;
+pub mod params;
+pub mod preset;
 pub mod projection_mapping;
+pub mod seeds;
+pub mod svg;
+mod transport;
 pub mod utils;
 
 pub use crate::artwork::{Artwork, Options};
+use crate::{
+    assets::Assets,
+    audio::live::LiveAnalyzer,
+    batch::Batch,
+    feedback::{Feedback, PrerollStep},
+    footage::Footage,
+    frame_writer::{FrameWriter, WriterConfig},
+    gpu_timer::GpuTimer,
+    gui::Gui,
+    hud::Hud,
+    layers::Layers,
+    midi::Midi,
+    osc::OscServer,
+    params::Params,
+    preset::{Preset, PresetLibrary},
+    seeds::{Bookmarks, SeedHistory},
+    svg::VectorDraw,
+    transport::Transport,
+};
 use nannou::{
     prelude::*,
+    rand::{rngs::StdRng, SeedableRng},
     wgpu::{self, TextureViewDimension},
+    winit::event::WindowEvent,
 };
 
 /// Frame per second for the animations.
 pub const FPS: u32 = 60;
 /// Default length of an animation.
 pub const N_SEC: u32 = 10;
+/// Width of the thumbnails of the bookmarked seeds, in pixels.
+const THUMBNAIL_SIZE: u32 = 256;
 /// The wgpu default texture format.
 const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 
@@ -63,13 +106,13 @@ const VERTICES: [Vertex; 4] = [
 
 /// The model base that all animations should use.
 pub struct BaseModel {
-    sample_per_frame: i32,
-    shutter_angle: f64,
+    /// The options currently in use, kept so they can be saved in a preset.
+    options: Options,
     uniforms: wgpu::Buffer,
-    texture_view: wgpu::TextureView,
     texture_accumulate_view: wgpu::TextureView,
-    sampler: wgpu::Sampler,
-    bind_group_layout: wgpu::BindGroupLayout,
+    /// Binds the texture drawn to, its sampler and the uniforms to the
+    /// accumulation shader. None of them change, so it is built only once.
+    bind_group: wgpu::BindGroup,
     render_pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
 
@@ -79,15 +122,59 @@ pub struct BaseModel {
     pub texture: wgpu::Texture,
     /// Create a `Draw` instance for drawing to our texture.
     pub draw: nannou::Draw,
+    /// Draws to `draw` and records the primitives for the SVG export.
+    pub vector: VectorDraw,
+    /// The layers composited over `draw`, if the artwork declared any.
+    pub layers: Layers,
 
     /// Holds the number of the frame being run. This allows resetting the
     /// animation when starting a recording.
     current_frame: u32,
     recording: bool,
+    /// Playback of the animation in the preview window.
+    transport: Transport,
     pub seed: i32,
+    /// The seeds used in this session.
+    seed_history: SeedHistory,
+    /// The seed the state of the artwork was last built from.
+    reseeded: i32,
+    /// The favourite seeds of this artwork.
+    bookmarks: Bookmarks,
+    /// Whether the current seed should be bookmarked with a thumbnail of the
+    /// next rendered frame.
+    bookmark_requested: bool,
+    /// Whether the geometry of the current frame should be exported to SVG.
+    svg_requested: bool,
+    /// The frames rendered instead of the preview for a contact sheet or a
+    /// golden-image test, if any.
+    batch: Option<Batch>,
 
+    /// The parameters exposed by the artwork.
+    pub params: Params,
+    /// The presets saved for this artwork.
+    presets: PresetLibrary,
+    /// The control panel shown on top of the preview.
+    gui: Gui,
+    /// The overlay with the state of the preview.
+    hud: Hud,
+    /// Times the frames on the GPU for the overlay, if the GPU supports it.
+    gpu_timer: Option<GpuTimer>,
+    /// The MIDI input controlling the parameters, if enabled.
+    midi: Option<Midi>,
+    /// The OSC server controlling the artwork remotely, if enabled.
+    osc: Option<OscServer>,
+    /// The live audio input, analysed at each update, if enabled.
+    pub audio_input: Option<LiveAnalyzer>,
+
+    /// The previous frame, if the artwork enabled it.
+    pub feedback: Option<Feedback>,
+    /// The images, fonts, audio and data files loaded by the artwork.
+    pub assets: Assets,
     /// Holds extra textures that can be used in the animation.
     pub extra_tex: Option<Vec<wgpu::Texture>>,
+    /// The footage declared in the options, in the same order. Their textures
+    /// show the time being drawn.
+    pub footage: Vec<Footage>,
 
     /// The texture that will accumulate frames for the motion blur
     texture_accumulate: wgpu::Texture,
@@ -96,37 +183,80 @@ pub struct BaseModel {
     renderer: nannou::draw::Renderer,
     /// The type used to capture the texture.
     texture_capturer: wgpu::TextureCapturer,
+    /// Writes the recorded frames to PNG files.
+    frame_writer: FrameWriter,
     /// The type used to reshape the texture. We draw the animation in 4K but
     /// only display a window of 540 time 540 pixels.
     texture_reshaper: wgpu::TextureReshaper,
 }
 
+impl BaseModel {
+    /// A random number generator seeded with `seed`, to build the random state
+    /// of the artwork. See [`Artwork::reseed`].
+    pub fn rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.seed as u64)
+    }
+}
+
 pub fn make_recorder_app<T: 'static + Artwork>() -> nannou::app::Builder<T> {
     nannou::app(model).update(update).exit(exit)
 }
 
 fn model<T: 'static + Artwork>(app: &App) -> T {
-    T::new(make_base_model::<T>(app, T::get_options()))
+    let mut model = T::new(make_base_model::<T>(app, T::get_options()));
+    // The preset is applied once the artwork registered its parameters.
+    if let Some(name) = preset::preset_from_args() {
+        match model.get_mut_model().presets.load(&name) {
+            Ok(preset) => apply_preset(app, model.get_mut_model(), preset),
+            Err(e) => eprintln!("Could not load preset {}: {}", name, e),
+        }
+    }
+    let n_frames = FPS * model.n_sec().unwrap_or(N_SEC);
+    if std::env::args().any(|a| a == "--loop-check-export") {
+        let passed = loop_check::check_export(&capture_directory(app), n_frames);
+        std::process::exit(if passed { 0 } else { 1 });
+    }
+    let seed = model.get_model().seed;
+    model.get_mut_model().batch = Batch::from_args(n_frames, seed);
+    model
 }
 
 pub fn make_base_model<T: 'static + Artwork>(app: &App, options: Option<Options>) -> BaseModel {
-    // Lets write to a 4K UHD texture.
-    let texture_size = [2160, 2160];
+    // Lets write to a 4K UHD texture, or to a small one for golden-image
+    // tests, which compare small images.
+    let texture_size = if golden::requested() {
+        [golden::IMAGE_SIZE; 2]
+    } else {
+        [2160, 2160]
+    };
+
+    // Timestamp queries time the frames for the overlay, where supported.
+    let mut device_descriptor = wgpu::default_device_descriptor();
+    let adapter = app
+        .wgpu_adapters()
+        .get_or_request(wgpu::default_adapter_request_options(), app.instance());
+    if let Some(adapter) = adapter {
+        device_descriptor.features |=
+            adapter.adapter().features() & wgpu::Features::TIMESTAMP_QUERY;
+    }
 
     // Create the window.
     let [win_w, win_h] = [texture_size[0] / 4, texture_size[1] / 4];
     let w_id = app
         .new_window()
         .size(win_w, win_h)
+        .device_descriptor(device_descriptor)
         .title("nannou")
         .view::<T>(view)
         .key_pressed::<T>(key_pressed)
+        .raw_event::<T>(raw_window_event)
         .build()
         .unwrap();
     let window = app.window(w_id).unwrap();
 
     // Retrieve the wgpu device.
     let device = window.device();
+    let gpu_timer = GpuTimer::new(device, window.queue());
     // Create our custom texture.
     let sample_count = window.msaa_samples();
     let texture = wgpu::TextureBuilder::new()
@@ -153,8 +283,12 @@ pub fn make_base_model<T: 'static + Artwork>(app: &App, options: Option<Options>
     let depth_texture = create_depth_texture(device, texture_size, DEPTH_FORMAT, sample_count);
     let depth_texture_view = depth_texture.view().build();
 
+    let feedback = T::feedback_preroll().map(|preroll| Feedback::new(device, &texture, preroll));
+    let layers = Layers::new(device, &texture, T::layers());
+
     // Create our `Draw` instance and a renderer for it.
     let draw = nannou::Draw::new();
+    let vector = VectorDraw::new(draw.clone());
     let descriptor = texture.descriptor();
     let renderer =
         nannou::draw::RendererBuilder::new().build_from_texture_descriptor(device, descriptor);
@@ -185,13 +319,19 @@ pub fn make_base_model<T: 'static + Artwork>(app: &App, options: Option<Options>
 
     // Uniforms to be passed to the shaders
     let uniforms_bytes = uniforms_as_bytes(&uniforms);
-    let usage = wgpu::BufferUsages::UNIFORM;
+    let usage = wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST;
     let buffer = device.create_buffer_init(&BufferInitDescriptor {
         label: None,
         contents: uniforms_bytes,
         usage,
     });
 
+    let bind_group = wgpu::BindGroupBuilder::new()
+        .texture_view(&texture_view)
+        .sampler(&sampler)
+        .buffer::<Uniforms>(&buffer, 0..1)
+        .build(device, &bind_group_layout);
+
     // Render pipeline layout construction
     let desc = wgpu::PipelineLayoutDescriptor {
         label: Some("The pipeline layout"),
@@ -233,87 +373,272 @@ pub fn make_base_model<T: 'static + Artwork>(app: &App, options: Option<Options>
         sample_count,
         dst_format,
     );
-    let extra_texture = if let Some(name) = options.extra_tex {
-        let assets = app.assets_path().unwrap();
-        Some(
-            name.iter()
-                .map(|n| wgpu::Texture::from_path(app, assets.join(n)).unwrap())
-                .collect(),
-        )
-    } else {
-        None
-    };
+    let assets_directory = app
+        .assets_path()
+        .or_else(|_| app.project_path().map(|path| path.join("assets")))
+        .unwrap_or_else(|e| {
+            eprintln!("Could not find the assets directory: {}", e);
+            std::path::PathBuf::from("assets")
+        });
+    // Missing textures are reported and replaced by a transparent one, so that
+    // the others keep their index.
+    let extra_texture = options.extra_tex.as_ref().map(|names| {
+        names
+            .iter()
+            .map(|n| {
+                wgpu::Texture::from_path(app, assets_directory.join(n)).unwrap_or_else(|e| {
+                    eprintln!("Could not load the texture {}: {}", n, e);
+                    wgpu::TextureBuilder::new()
+                        .size([1, 1])
+                        .format(wgpu::TextureFormat::Rgba8UnormSrgb)
+                        .usage(wgpu::TextureUsages::TEXTURE_BINDING)
+                        .build(device)
+                })
+            })
+            .collect()
+    });
+
+    let footage = options
+        .footage
+        .iter()
+        .flatten()
+        .map(|name| {
+            let path = assets_directory.join(name);
+            Footage::open(device, &path).unwrap_or_else(|e| {
+                eprintln!("Could not load the footage {}: {}", name, e);
+                Footage::missing(device)
+            })
+        })
+        .collect();
 
     // Make sure the directory where we will save images to exists.
     std::fs::create_dir_all(capture_directory(app)).unwrap();
+    let presets = PresetLibrary::new(preset_directory(app));
+    let bookmarks = Bookmarks::load(bookmark_directory(app)).unwrap_or_else(|e| {
+        eprintln!("Could not load the bookmarked seeds: {}", e);
+        Bookmarks::empty(bookmark_directory(app))
+    });
+    let seed = random();
+    let gui = Gui::new(&window);
+    let midi = Midi::from_args(midi_mapping_path(app));
+    let osc = OscServer::from_args();
+    let audio_input = LiveAnalyzer::from_args();
     BaseModel {
-        sample_per_frame: options.sample_per_frame,
-        shutter_angle: options.shutter_angle,
+        options,
         uniforms: buffer,
-        texture_view,
         texture_accumulate_view,
-        sampler,
-        bind_group_layout,
+        bind_group,
         vertex_buffer,
         render_pipeline,
         texture,
         texture_accumulate,
         draw,
+        vector,
+        layers,
         renderer,
         texture_capturer,
+        frame_writer: FrameWriter::new(WriterConfig::from_args()),
         texture_reshaper,
         current_frame: 0,
         recording: false,
-        seed: random(),
+        transport: Transport::new(),
+        seed,
+        seed_history: SeedHistory::new(seed),
+        reseeded: seed,
+        bookmarks,
+        bookmark_requested: false,
+        svg_requested: false,
+        batch: None,
+        params: Params::default(),
+        presets,
+        gui,
+        hud: Hud::new(),
+        gpu_timer,
+        midi,
+        osc,
+        audio_input,
         depth_texture_view,
+        feedback,
+        assets: Assets::new(assets_directory, window.device_queue_pair().clone()),
         extra_tex: extra_texture,
+        footage,
     }
 }
 
-fn update<T: Artwork>(app: &App, model: &mut T, _update: Update) {
+fn update<T: Artwork>(app: &App, model: &mut T, update: Update) {
     // Create a `Rect` for our texture to help with drawing.
     let [w, _h] = model.get_model().texture.size();
     let n_sec = model.n_sec().unwrap_or(N_SEC);
+    gui::update(app, model.get_mut_model(), &update, FPS * n_sec);
+    let base_model = model.get_mut_model();
+    if let Some(midi) = &mut base_model.midi {
+        midi.update(&mut base_model.params, update.since_last.as_secs_f32());
+    }
+    if let Some(audio_input) = &mut base_model.audio_input {
+        audio_input.update(update.since_last.as_secs_f64());
+    }
+    if let Some(commands) = base_model.osc.as_ref().map(OscServer::commands) {
+        for command in commands {
+            osc::apply(base_model, command, FPS * n_sec);
+        }
+    }
+    // Assets are not reloaded while recording or rendering a batch, so that
+    // all the frames use the same files.
+    if !base_model.recording && base_model.batch.is_none() {
+        base_model.assets.reload_changed();
+    }
+    // Seeds can change from keys, the panel, OSC or presets.
+    base_model.seed_history.record(base_model.seed);
+    let batch_cell = base_model.batch.as_mut().and_then(Batch::next_cell);
+    if let Some((_, cell)) = batch_cell {
+        base_model.seed = cell.seed;
+    }
+    // Each batch cell renders the same frame whatever was rendered before it.
+    if batch_cell.is_some() || base_model.seed != base_model.reseeded {
+        base_model.reseeded = base_model.seed;
+        model.reseed();
+    }
     // Use the frame number to animate, ensuring we get a constant update time.
     // Render our drawing to the texture.
     let window = app.main_window();
     let device = window.device();
 
-    let elapsed_frames = if model.get_model().recording {
-        model.get_model().current_frame
+    let base_model = model.get_mut_model();
+    let transport = &mut base_model.transport;
+    transport.advance(update.since_last.as_secs_f64(), FPS, FPS * n_sec);
+    if transport.scrubbing && !base_model.gui.wants_pointer() {
+        transport.scrub(app.mouse.x, w as f32, FPS * n_sec);
+    }
+    let elapsed_frames = if let Some((_, cell)) = batch_cell {
+        cell.frame
+    } else if base_model.recording {
+        base_model.current_frame
     } else {
-        let pos = 2. * (4. * app.mouse.x + (w as f32)) / w as f32;
-        (pos * (FPS * n_sec) as f32) as u32 % (FPS * n_sec)
+        transport.frame(FPS * n_sec)
     };
-    let n_sample_per_frame = model.get_model().sample_per_frame;
-    for i in 0..n_sample_per_frame {
-        let t: f64 = map_range(
-            elapsed_frames as f64
-                + i as f64 * model.get_model().shutter_angle
-                    / model.get_model().sample_per_frame as f64,
-            0.,
-            (FPS * n_sec) as f64,
-            0.,
-            1.,
-        );
+    // Feedback depends on the previous frames, so it is cleared and the frames
+    // before the first captured one are drawn again to make the capture
+    // deterministic.
+    let starts_capture = batch_cell.is_some() || (base_model.recording && elapsed_frames == 0);
+    let preroll = match &base_model.feedback {
+        Some(feedback) if starts_capture => {
+            feedback::preroll_steps(elapsed_frames, feedback.preroll(), FPS * n_sec)
+        }
+        _ => vec![],
+    };
+    for step in preroll {
+        let ce_desc = wgpu::CommandEncoderDescriptor {
+            label: Some("feedback pre-roll"),
+        };
+        let mut encoder = device.create_command_encoder(&ce_desc);
+        match step {
+            PrerollStep::Clear => {
+                if let Some(feedback) = &mut model.get_mut_model().feedback {
+                    feedback.encode_clear(&mut encoder);
+                }
+            }
+            PrerollStep::Frame(frame) => {
+                encode_frame(device, &mut encoder, frame, FPS * n_sec, model)
+            }
+        }
+        window.queue().submit(Some(encoder.finish()));
+    }
+    let snapshot = render_frame(device, &window, elapsed_frames, FPS * n_sec, model);
 
-        render_pass(device, &window, t, model, i == 0);
+    let base_model = model.get_mut_model();
+    let hud = &mut base_model.hud;
+    hud.frame = elapsed_frames;
+    hud.n_frames = FPS * n_sec;
+    if let Some(timer) = &mut base_model.gpu_timer {
+        timer.poll(device);
+        hud.gpu_time = timer.last();
     }
 
-    let ce_desc = wgpu::CommandEncoderDescriptor {
-        label: Some("save texture renderer"),
-    };
-    let mut encoder = device.create_command_encoder(&ce_desc);
+    if let Some((index, _)) = batch_cell {
+        render_batch_cell(app, model.get_model(), index, snapshot)
+    } else if model.get_model().recording {
+        record_frame(app, elapsed_frames, model, snapshot)
+    } else if model.get_model().bookmark_requested {
+        bookmark_seed(model.get_mut_model(), elapsed_frames, snapshot)
+    }
+    if model.get_model().svg_requested {
+        export_svg(app, model.get_mut_model(), elapsed_frames);
+    }
+}
 
-    let snapshot = model.get_model().texture_capturer.capture(
-        device,
-        &mut encoder,
-        &model.get_model().texture_accumulate,
-    );
-    window.queue().submit(Some(encoder.finish()));
+/// Write the primitives recorded by the `VectorDraw` while rendering `frame`,
+/// in its last motion blur sample, to an SVG file. The frame is not drawn
+/// again, so artworks whose state advances as they draw stay in step.
+fn export_svg(app: &App, base_model: &mut BaseModel, frame: u32) {
+    base_model.svg_requested = false;
+    let path = svg_directory(app).join(format!("{}-{}.svg", base_model.seed, frame));
+    let svg = base_model.vector.to_svg(base_model.texture.size());
+    let written =
+        std::fs::create_dir_all(svg_directory(app)).and_then(|_| std::fs::write(&path, svg));
+    match written {
+        Ok(()) => println!("Exported {}", path.display()),
+        Err(e) => eprintln!("Could not export {}: {}", path.display(), e),
+    }
+}
 
-    if model.get_model().recording {
-        record_frame(app, elapsed_frames, model, snapshot)
+/// Capture the rendered frame of the batch, and process the frames and quit
+/// once all its cells are rendered. A failed batch, like a golden-image test
+/// that did not pass, exits with an error status.
+fn render_batch_cell(
+    app: &App,
+    base_model: &BaseModel,
+    index: usize,
+    snapshot: wgpu::TextueSnapshot,
+) {
+    let batch = match &base_model.batch {
+        Some(batch) => batch,
+        None => return,
+    };
+    batch.capture(index, snapshot);
+    if !batch.is_rendered() {
+        return;
+    }
+    let window = app.main_window();
+    base_model
+        .texture_capturer
+        .await_active_snapshots(window.device())
+        .unwrap();
+    if batch.finish(app) {
+        app.quit();
+    } else {
+        std::process::exit(1);
+    }
+}
+
+/// Bookmark the current seed, with a thumbnail of the rendered frame.
+fn bookmark_seed(base_model: &mut BaseModel, frame: u32, snapshot: wgpu::TextueSnapshot) {
+    base_model.bookmark_requested = false;
+    let seed = base_model.seed;
+    let path = match base_model.bookmarks.add(seed, frame) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("Could not bookmark seed {}: {}", seed, e);
+            return;
+        }
+    };
+    println!("Bookmarked seed {}", seed);
+    let read = snapshot.read(move |result| {
+        let image = match result {
+            Ok(image) => image.to_owned(),
+            Err(e) => {
+                eprintln!("Could not read the thumbnail of seed {}: {}", seed, e);
+                return;
+            }
+        };
+        let (w, h) = image.dimensions();
+        let thumbnail =
+            nannou::image::imageops::thumbnail(&image, THUMBNAIL_SIZE, THUMBNAIL_SIZE * h / w);
+        if let Err(e) = thumbnail.save(&path) {
+            eprintln!("Could not save the thumbnail of seed {}: {}", seed, e);
+        }
+    });
+    if let Err(e) = read {
+        eprintln!("Could not read the thumbnail of seed {}: {:?}", seed, e);
     }
 }
 
@@ -326,75 +651,144 @@ fn record_frame<T: Artwork>(
     let path = capture_directory(app)
         .join(elapsed_frames.to_string())
         .with_extension("png");
-    snapshot
-        .read(move |result| {
-            let image = result.expect("failed to map texture memory").to_owned();
-            image
-                .save(&path)
-                .expect("failed to save texture to png image");
-        })
-        .unwrap();
     let n_sec = model.n_sec().unwrap_or(N_SEC);
     let base_model = model.get_mut_model();
+    // Wait for room in the queue, letting the GPU finish reading back frames.
+    let window = app.main_window();
+    let device = window.device();
+    let writer = &base_model.frame_writer;
+    writer.reserve(|| device.poll(wgpu::Maintain::Poll));
+    writer.write(path, snapshot);
+    let errors = writer.take_errors();
+    for error in &errors {
+        eprintln!("{}", error);
+    }
+    if !errors.is_empty() {
+        eprintln!("Recording stopped");
+        base_model.recording = false;
+        return;
+    }
     base_model.current_frame += 1;
-    if base_model.current_frame > FPS * n_sec {
+    // The frame at time 1 is the first one again, so it is not recorded.
+    if base_model.current_frame >= FPS * n_sec {
         base_model.recording = false;
     }
 }
 
-fn render_pass<T: Artwork>(
+/// Render all the motion blur samples of a frame and capture the result.
+///
+/// The samples, their accumulation and the capture are all recorded in a
+/// single command encoder, submitted once. The `Draw` of each sample is encoded
+/// as soon as it is drawn, so the next sample can reuse it.
+fn render_frame<T: Artwork>(
     device: &wgpu::Device,
     window: &Window,
+    frame: u32,
+    n_frames: u32,
+    model: &mut T,
+) -> wgpu::TextueSnapshot {
+    let ce_desc = wgpu::CommandEncoderDescriptor {
+        label: Some("frame renderer"),
+    };
+    let mut encoder = device.create_command_encoder(&ce_desc);
+    // The frames are only timed while the overlay shows the time.
+    let timed = model.get_model().hud.visible;
+    let base_model = model.get_mut_model();
+    if let Some(timer) = base_model.gpu_timer.as_mut().filter(|_| timed) {
+        timer.begin(&mut encoder);
+    }
+    encode_frame(device, &mut encoder, frame, n_frames, model);
+    let base_model = model.get_mut_model();
+    if let Some(timer) = base_model.gpu_timer.as_mut().filter(|_| timed) {
+        timer.end(&mut encoder);
+    }
+    let snapshot =
+        base_model
+            .texture_capturer
+            .capture(device, &mut encoder, &base_model.texture_accumulate);
+    window.queue().submit(Some(encoder.finish()));
+    if let Some(timer) = &mut base_model.gpu_timer {
+        timer.submitted();
+    }
+    snapshot
+}
+
+/// Record the rendering of all the motion blur samples of a frame, and the
+/// copy of the frame to the feedback texture.
+fn encode_frame<T: Artwork>(
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    frame: u32,
+    n_frames: u32,
+    model: &mut T,
+) {
+    let n_sample_per_frame = model.get_model().options.sample_per_frame;
+    for i in 0..n_sample_per_frame {
+        let t: f64 = map_range(
+            frame as f64
+                + i as f64 * model.get_model().options.shutter_angle / n_sample_per_frame as f64,
+            0.,
+            n_frames as f64,
+            0.,
+            1.,
+        );
+        render_pass(device, encoder, t, model, i == 0);
+    }
+    if let Some(feedback) = &mut model.get_mut_model().feedback {
+        feedback.encode_copy(encoder, frame);
+    }
+}
+
+/// Draw the artwork at time `t` and add it to the accumulated frame, clearing
+/// the accumulated frame first for the `first` sample.
+fn render_pass<T: Artwork>(
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
     t: f64,
     model: &mut T,
     first: bool,
 ) {
+    let duration = model.n_sec().unwrap_or(N_SEC) as f64;
+    let base_model = model.get_mut_model();
+    for footage in &mut base_model.footage {
+        footage.upload(device, encoder, t, duration);
+    }
+    base_model.vector.clear();
     model.draw_at_time(t);
     let base_model = model.get_mut_model();
-    let ce_desc = wgpu::CommandEncoderDescriptor {
-        label: Some("single pass texture renderer"),
-    };
-    let mut encoder = device.create_command_encoder(&ce_desc);
-    base_model.renderer.render_to_texture(
+    // With layers, the base drawing is composited with them into `texture`.
+    let target = base_model
+        .layers
+        .base_texture()
+        .unwrap_or(&base_model.texture);
+    base_model
+        .renderer
+        .render_to_texture(device, encoder, &base_model.draw, target);
+    base_model.layers.encode(
         device,
-        &mut encoder,
-        &base_model.draw,
-        &base_model.texture,
+        encoder,
+        &mut base_model.renderer,
+        &base_model.vertex_buffer,
     );
-    window.queue().submit(Some(encoder.finish()));
-
-    let bind_group = wgpu::BindGroupBuilder::new()
-        .texture_view(&base_model.texture_view)
-        .sampler(&base_model.sampler)
-        .buffer::<Uniforms>(&base_model.uniforms, 0..1)
-        .build(device, &base_model.bind_group_layout);
 
-    let ce_desc = wgpu::CommandEncoderDescriptor {
-        label: Some("accumulate texture renderer"),
-    };
-    let mut encoder = device.create_command_encoder(&ce_desc);
-    {
-        let tex_view = &base_model.texture_accumulate_view;
-        let mut render_pass = if first {
-            wgpu::RenderPassBuilder::new()
-                .color_attachment(tex_view, |color| color)
-                .depth_stencil_attachment(&base_model.depth_texture_view, |depth| depth)
-                .begin(&mut encoder)
-        } else {
-            wgpu::RenderPassBuilder::new()
-                .color_attachment(tex_view, |color| color.load_op(wgpu::LoadOp::Load))
-                .depth_stencil_attachment(&base_model.depth_texture_view, |depth| depth)
-                .begin(&mut encoder)
-        };
-        render_pass.set_bind_group(0, &bind_group, &[]);
-        render_pass.set_pipeline(&base_model.render_pipeline);
-        render_pass.set_vertex_buffer(0, base_model.vertex_buffer.slice(..));
-        let vertex_range = 0..VERTICES.len() as u32;
-        let instance_range = 0..1;
-        render_pass.draw(vertex_range, instance_range)
+    let tex_view = &base_model.texture_accumulate_view;
+    let mut render_pass = if first {
+        wgpu::RenderPassBuilder::new()
+            .color_attachment(tex_view, |color| color)
+            .depth_stencil_attachment(&base_model.depth_texture_view, |depth| depth)
+            .begin(encoder)
+    } else {
+        wgpu::RenderPassBuilder::new()
+            .color_attachment(tex_view, |color| color.load_op(wgpu::LoadOp::Load))
+            .depth_stencil_attachment(&base_model.depth_texture_view, |depth| depth)
+            .begin(encoder)
     };
-
-    window.queue().submit(Some(encoder.finish()));
+    render_pass.set_bind_group(0, &base_model.bind_group, &[]);
+    render_pass.set_pipeline(&base_model.render_pipeline);
+    render_pass.set_vertex_buffer(0, base_model.vertex_buffer.slice(..));
+    let vertex_range = 0..VERTICES.len() as u32;
+    let instance_range = 0..1;
+    render_pass.draw(vertex_range, instance_range)
 }
 
 fn create_render_pipeline(
@@ -444,13 +838,22 @@ fn create_bind_group_layout(
 }
 
 // Draw the state of your `Model` into the given `Frame` here.
-fn view<T: Artwork>(_app: &App, model: &T, frame: Frame) {
+fn view<T: Artwork>(app: &App, model: &T, frame: Frame) {
     // Sample the texture and write it to the frame.
-    let mut encoder = frame.command_encoder();
-    model
-        .get_model()
-        .texture_reshaper
-        .encode_render_pass(frame.texture_view(), &mut encoder);
+    {
+        let mut encoder = frame.command_encoder();
+        model
+            .get_model()
+            .texture_reshaper
+            .encode_render_pass(frame.texture_view(), &mut encoder);
+    }
+    // The overlays are drawn on the window only, never on the recorded texture.
+    hud::draw_to_frame(app, model.get_model(), &frame);
+    model.get_model().gui.draw_to_frame(&frame);
+}
+
+fn raw_window_event<T: Artwork>(_app: &App, model: &mut T, event: &WindowEvent) {
+    model.get_mut_model().gui.handle_raw_event(event);
 }
 
 // Wait for capture to finish.
@@ -458,11 +861,17 @@ fn exit<T: Artwork>(app: &App, model: T) {
     println!("Waiting for PNG writing to complete...");
     let window = app.main_window();
     let device = window.device();
-    model
-        .get_model()
+    let base_model = model.get_model();
+    base_model
         .texture_capturer
         .await_active_snapshots(device)
         .unwrap();
+    base_model
+        .frame_writer
+        .flush(|| device.poll(wgpu::Maintain::Poll));
+    for error in base_model.frame_writer.take_errors() {
+        eprintln!("{}", error);
+    }
     println!("Done!");
 }
 
@@ -473,6 +882,96 @@ fn capture_directory(app: &App) -> std::path::PathBuf {
         .join(app.exe_name().unwrap())
 }
 
+// The directory where the presets of the artwork are stored.
+fn preset_directory(app: &App) -> std::path::PathBuf {
+    app.project_path()
+        .expect("could not locate project_path")
+        .join("presets")
+        .join(app.exe_name().unwrap())
+}
+
+// The directory where the bookmarked seeds of the artwork are stored.
+fn bookmark_directory(app: &App) -> std::path::PathBuf {
+    app.project_path()
+        .expect("could not locate project_path")
+        .join("bookmarks")
+        .join(app.exe_name().unwrap())
+}
+
+// The directory where the contact sheets of the artwork are stored.
+fn contact_sheet_directory(app: &App) -> std::path::PathBuf {
+    app.project_path()
+        .expect("could not locate project_path")
+        .join("contact_sheets")
+        .join(app.exe_name().unwrap())
+}
+
+// The directory where the reference images of the artwork are stored.
+fn golden_directory(app: &App) -> std::path::PathBuf {
+    app.project_path()
+        .expect("could not locate project_path")
+        .join("tests")
+        .join("golden")
+        .join(app.exe_name().unwrap())
+}
+
+// The directory where the SVG exports of the artwork are stored.
+fn svg_directory(app: &App) -> std::path::PathBuf {
+    app.project_path()
+        .expect("could not locate project_path")
+        .join("svg")
+        .join(app.exe_name().unwrap())
+}
+
+// The file where the MIDI mappings of the artwork are stored.
+fn midi_mapping_path(app: &App) -> std::path::PathBuf {
+    app.project_path()
+        .expect("could not locate project_path")
+        .join("midi")
+        .join(app.exe_name().unwrap())
+        .with_extension("json")
+}
+
+/// Replace the options of a running artwork. The extra textures are only
+/// loaded at startup, so they are kept as they are.
+fn apply_options(app: &App, base_model: &mut BaseModel, options: Options) {
+    let uniforms = Uniforms {
+        chroma: options.chroma,
+        sample_per_frame: options.sample_per_frame,
+        noise_amout: options.noise_amount,
+    };
+    app.main_window()
+        .queue()
+        .write_buffer(&base_model.uniforms, 0, uniforms_as_bytes(&uniforms));
+    base_model.options = Options {
+        extra_tex: base_model.options.extra_tex.take(),
+        footage: base_model.options.footage.take(),
+        ..options
+    };
+}
+
+/// Start or stop recording. Recordings always start from the first frame.
+fn set_recording(base_model: &mut BaseModel, recording: bool) {
+    if recording && !base_model.recording {
+        base_model.current_frame = 0;
+    }
+    base_model.recording = recording;
+}
+
+fn apply_preset(app: &App, base_model: &mut BaseModel, preset: Preset) {
+    base_model.seed = preset.seed;
+    base_model.params.set_values(&preset.params);
+    apply_options(app, base_model, preset.options);
+}
+
+fn current_preset(base_model: &BaseModel) -> Preset {
+    Preset {
+        seed: base_model.seed,
+        params: base_model.params.values(),
+        options: base_model.options.clone(),
+    }
+}
+
 fn create_depth_texture(
     device: &wgpu::Device,
     size: [u32; 2],
@@ -488,17 +987,75 @@ fn create_depth_texture(
 }
 
 fn key_pressed<T: Artwork>(app: &App, model: &mut T, key: Key) {
+    let n_frames = FPS * model.n_sec().unwrap_or(N_SEC);
     let base_model = model.get_mut_model();
+    if base_model.gui.wants_keyboard() {
+        return;
+    }
     match key {
         Key::S => {
             base_model.seed = random();
         }
         Key::R => {
-            if base_model.recording {
-                base_model.recording = false;
+            let recording = !base_model.recording;
+            set_recording(base_model, recording);
+        }
+        Key::F1 => {
+            base_model.hud.visible = !base_model.hud.visible;
+        }
+        Key::F2 => {
+            base_model.gui.visible = !base_model.gui.visible;
+        }
+        Key::F3 => match &mut base_model.midi {
+            Some(midi) => midi.learn_next(&base_model.params),
+            None => println!("MIDI is not enabled, use --midi or --midi-file"),
+        },
+        Key::F5 => {
+            let preset = current_preset(base_model);
+            match base_model.presets.save_new(&preset) {
+                Ok(path) => println!("Saved preset to {}", path.display()),
+                Err(e) => eprintln!("Could not save preset: {}", e),
+            }
+        }
+        Key::Space => base_model.transport.toggle_playing(n_frames),
+        Key::Left => base_model.transport.step(-1, n_frames),
+        Key::Right => base_model.transport.step(1, n_frames),
+        Key::Up => base_model.transport.faster(),
+        Key::Down => base_model.transport.slower(),
+        Key::Home => base_model.transport.jump(0, n_frames),
+        Key::End => base_model.transport.jump(n_frames - 1, n_frames),
+        Key::Tab => base_model.transport.toggle_scrubbing(),
+        Key::Back => base_model.transport.looping = !base_model.transport.looping,
+        Key::PageUp => {
+            if let Some(seed) = base_model.seed_history.previous() {
+                base_model.seed = seed;
+            }
+        }
+        Key::PageDown => {
+            if let Some(seed) = base_model.seed_history.next() {
+                base_model.seed = seed;
+            }
+        }
+        Key::F8 => {
+            base_model.bookmark_requested = true;
+        }
+        Key::F9 => {
+            base_model.svg_requested = true;
+        }
+        Key::F6 | Key::F7 => {
+            base_model.presets.refresh();
+            let loaded = if key == Key::F6 {
+                base_model.presets.previous()
             } else {
-                base_model.recording = true;
-                base_model.current_frame = 0;
+                base_model.presets.next()
+            };
+            match loaded {
+                Some(Ok((name, preset))) => {
+                    println!("Loaded preset {}", name);
+                    apply_preset(app, base_model, preset);
+                }
+                Some(Err(e)) => eprintln!("Could not load preset: {}", e),
+                None => println!("No preset saved for this artwork"),
             }
         }
         _ => {}
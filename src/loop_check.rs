@@ -0,0 +1,308 @@
+//! Check that an artwork loops seamlessly.
+//!
+//! With `--loop-check`, the frames around the loop point and a few pairs of
+//! consecutive frames in the middle of the animation are rendered at the
+//! current seed. The change from the last frame to the first one is compared
+//! with the typical change between two frames: a much larger change is a jump
+//! in the loop, and no change at all means the first frame is repeated, which
+//! stalls the loop. The frame at time 1 is also compared with the frame at
+//! time 0, which should be identical.
+//!
+//! `--loop-check-export` checks the frames of the last recording instead,
+//! which also catches exports with a wrong number of frames:
+//!
+//! ``` sh
+//! cargo run --release --example tiling -- --loop-check
+//! cargo run --release --example tiling -- --loop-check-export
+//! ```
+use crate::batch::{Batch, Cell, Purpose};
+use nannou::image::{self, imageops, RgbaImage};
+use std::{collections::BTreeMap, path::Path};
+
+/// The width of the compared frames, in pixels.
+const IMAGE_SIZE: u32 = 512;
+/// The change below which two frames are considered identical.
+const STILL: f32 = 0.0005;
+/// The largest change at the loop point, relative to the typical change.
+const MAX_SEAM_RATIO: f32 = 3.;
+/// The smallest change at the loop point, relative to the typical change.
+const MIN_SEAM_RATIO: f32 = 0.2;
+
+/// The mean absolute difference of the color channels of two images of the
+/// same size, between 0 and 1.
+fn difference(a: &RgbaImage, b: &RgbaImage) -> f32 {
+    let total: u64 = a
+        .pixels()
+        .zip(b.pixels())
+        .map(|(a, b)| {
+            (0..3)
+                .map(|c| (a[c] as i32 - b[c] as i32).unsigned_abs() as u64)
+                .sum::<u64>()
+        })
+        .sum();
+    total as f32 / (3 * 255 * a.pixels().len().max(1)) as f32
+}
+
+/// The first frames of the pairs of consecutive frames measuring the typical
+/// change in a loop of `n_frames` frames, the last pair ending on the last
+/// frame. No pair goes past the last frame, even in short loops.
+fn pairs(n_frames: u32) -> Vec<u32> {
+    let last = n_frames - 2;
+    let mut starts: Vec<u32> = [0, n_frames / 4, n_frames / 2, 3 * n_frames / 4, last]
+        .into_iter()
+        .map(|f| f.min(last))
+        .collect();
+    starts.sort_unstable();
+    starts.dedup();
+    starts
+}
+
+/// The frames to compare for a loop of `n_frames` frames.
+fn frames(n_frames: u32) -> Vec<u32> {
+    let mut frames: Vec<u32> = pairs(n_frames)
+        .into_iter()
+        .flat_map(|f| [f, f + 1])
+        .chain([n_frames])
+        .collect();
+    frames.sort_unstable();
+    frames.dedup();
+    frames
+}
+
+/// How the frames change at the loop point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Report {
+    /// The change from the last frame to the first one.
+    pub seam: f32,
+    /// The median change between consecutive frames away from the loop point.
+    pub typical: f32,
+    /// The difference between the frames at time 1 and time 0, if the frame
+    /// at time 1 was rendered.
+    pub end_mismatch: Option<f32>,
+}
+
+impl Report {
+    /// Measure a loop of `n_frames` frames from `images`, which contain at
+    /// least the frames given by [`frames`], except maybe the last one.
+    pub fn measure(images: &BTreeMap<u32, RgbaImage>, n_frames: u32) -> Option<Report> {
+        let diff = |a: u32, b: u32| Some(difference(images.get(&a)?, images.get(&b)?));
+        let mut changes: Vec<f32> = pairs(n_frames)
+            .into_iter()
+            .map(|f| diff(f, f + 1))
+            .collect::<Option<_>>()?;
+        changes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        Some(Report {
+            seam: diff(n_frames - 1, 0)?,
+            typical: changes[changes.len() / 2],
+            end_mismatch: diff(n_frames, 0),
+        })
+    }
+
+    /// The problems of the loop, if any.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+        let ratio = self.seam / self.typical.max(f32::EPSILON);
+        if self.seam > STILL && ratio > MAX_SEAM_RATIO {
+            problems.push(format!(
+                "The loop jumps: the last frame changes {:.1} times more than usual into the first one",
+                ratio
+            ));
+        }
+        if self.typical > STILL && ratio < MIN_SEAM_RATIO {
+            problems.push(
+                "The last frame repeats the first one, which stalls the loop for a frame".into(),
+            );
+        }
+        if let Some(mismatch) = self.end_mismatch {
+            if mismatch > STILL.max(self.typical / 2.) {
+                problems.push("The frame at time 1 differs from the frame at time 0".into());
+            }
+        }
+        problems
+    }
+
+    fn print(&self, problems: &[String]) {
+        println!(
+            "Change at the loop point: {:.5}, typical change: {:.5}",
+            self.seam, self.typical
+        );
+        if problems.is_empty() {
+            println!("The animation loops seamlessly");
+        }
+        for problem in problems {
+            eprintln!("{}", problem);
+        }
+    }
+}
+
+/// A check of the loop point of an artwork.
+pub(crate) struct LoopCheck {
+    n_frames: u32,
+}
+
+impl LoopCheck {
+    /// Read `--loop-check` from the command line, to check the loop of
+    /// `n_frames` frames rendered at `seed`.
+    pub fn from_args(n_frames: u32, seed: i32) -> Option<Batch> {
+        if !std::env::args().any(|a| a == "--loop-check") || n_frames < 4 {
+            return None;
+        }
+        let cells = frames(n_frames)
+            .into_iter()
+            .map(|frame| Cell { seed, frame })
+            .collect();
+        let check = LoopCheck { n_frames };
+        Some(Batch::new(cells, IMAGE_SIZE, Purpose::LoopCheck(check)))
+    }
+
+    pub fn finish(&self, cells: &[Cell], images: &[RgbaImage]) -> bool {
+        let images = cells
+            .iter()
+            .map(|cell| cell.frame)
+            .zip(images.iter().cloned())
+            .collect();
+        match Report::measure(&images, self.n_frames) {
+            Some(report) => {
+                let problems = report.problems();
+                report.print(&problems);
+                problems.is_empty()
+            }
+            None => {
+                eprintln!("Some frames could not be rendered");
+                false
+            }
+        }
+    }
+}
+
+/// Check the frames recorded in `directory`, named after their number, for a
+/// loop of `n_frames` frames.
+pub(crate) fn check_export(directory: &Path, n_frames: u32) -> bool {
+    let count = std::fs::read_dir(directory).map_or(0, |entries| {
+        entries
+            .filter_map(Result::ok)
+            .filter(|e| e.path().extension().map_or(false, |ext| ext == "png"))
+            .count() as u32
+    });
+    if count < 4 {
+        eprintln!("No recording to check in {}", directory.display());
+        return false;
+    }
+    let mut images = BTreeMap::new();
+    for frame in frames(count).into_iter().filter(|f| *f < count) {
+        let path = directory.join(frame.to_string()).with_extension("png");
+        match image::open(&path) {
+            Ok(image) => {
+                let image = image.to_rgba8();
+                let (w, h) = image.dimensions();
+                let image = imageops::thumbnail(&image, IMAGE_SIZE, IMAGE_SIZE * h / w);
+                images.insert(frame, image);
+            }
+            Err(e) => {
+                eprintln!("Could not read {}: {}", path.display(), e);
+                return false;
+            }
+        }
+    }
+    let mut problems = vec![];
+    if count != n_frames {
+        problems.push(format!(
+            "The recording has {} frames instead of {}",
+            count, n_frames
+        ));
+    }
+    let report = match Report::measure(&images, count) {
+        Some(report) => report,
+        None => {
+            eprintln!("Some frames are missing in {}", directory.display());
+            return false;
+        }
+    };
+    problems.extend(report.problems());
+    report.print(&problems);
+    problems.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_export, frames, pairs, Report};
+    use nannou::image::{Rgba, RgbaImage};
+    use std::collections::BTreeMap;
+
+    /// Frames of a gray that brightens by `step` per frame, from `start`.
+    fn ramp(frames: &[u32], start: u8, step: u8) -> BTreeMap<u32, RgbaImage> {
+        frames
+            .iter()
+            .map(|f| {
+                let v = start + step * *f as u8;
+                (*f, RgbaImage::from_pixel(4, 4, Rgba([v, v, v, 255])))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn frames_around_the_loop_point() {
+        assert_eq!(frames(40), vec![0, 1, 10, 11, 20, 21, 30, 31, 38, 39, 40]);
+        assert_eq!(frames(4), vec![0, 1, 2, 3, 4]);
+        assert_eq!(pairs(4), vec![0, 1, 2]);
+        assert_eq!(pairs(5), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn measure_short_loops() {
+        // A loop of 4 frames going up and down, without the frame at time 1,
+        // which is not part of an export.
+        let n = 4;
+        let images: BTreeMap<u32, RgbaImage> = [0, 2, 4, 2]
+            .into_iter()
+            .enumerate()
+            .map(|(f, v)| (f as u32, RgbaImage::from_pixel(4, 4, Rgba([v, v, v, 255]))))
+            .collect();
+        let report = Report::measure(&images, n).unwrap();
+        assert_eq!(report.end_mismatch, None);
+        assert!(report.problems().is_empty());
+
+        let directory =
+            std::env::temp_dir().join(format!("artworks-loop-check-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        for (frame, image) in &images {
+            image
+                .save(directory.join(format!("{}.png", frame)))
+                .unwrap();
+        }
+        assert!(check_export(&directory, n));
+        assert!(!check_export(&directory, n + 1));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn detect_jumps_and_stalls() {
+        let n = 40;
+        // A ramp that never comes back jumps at the loop point.
+        let images = ramp(&frames(n), 0, 2);
+        let report = Report::measure(&images, n).unwrap();
+        assert!((report.typical - 2. / 255.).abs() < 1e-6);
+        assert_eq!(report.problems().len(), 2);
+
+        // A triangle wave loops, its last frame being one step from the first.
+        let triangle = |f: u32| 2 * (f as i32 - 20).unsigned_abs() as u8;
+        let mut images: BTreeMap<u32, RgbaImage> = frames(n)
+            .into_iter()
+            .map(|f| {
+                let v = triangle(f % n);
+                (f, RgbaImage::from_pixel(4, 4, Rgba([v, v, v, 255])))
+            })
+            .collect();
+        assert!(Report::measure(&images, n).unwrap().problems().is_empty());
+
+        // An extra frame repeating the first one stalls the loop.
+        images.insert(39, images[&0].clone());
+        let problems = Report::measure(&images, n).unwrap().problems();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("repeats"));
+
+        // A still image loops.
+        let still = ramp(&frames(n), 10, 0);
+        assert!(Report::measure(&still, n).unwrap().problems().is_empty());
+    }
+}
@@ -0,0 +1,539 @@
+//! MIDI input mapped to the parameters of an artwork.
+//!
+//! MIDI is enabled from the command line, either with a hardware or virtual
+//! port, or by replaying a recorded MIDI file in a loop instead of a
+//! controller:
+//!
+//! ``` sh
+//! # Connect to the first port whose name contains "nanoKONTROL".
+//! cargo run --release --example flowfield -- --midi nanoKONTROL
+//! # Create a virtual ALSA sequencer port named "artworks".
+//! cargo run --release --example flowfield -- --midi virtual
+//! # Replay a MIDI file.
+//! cargo run --release --example flowfield -- --midi-file controls.mid
+//! ```
+//!
+//! Pressing F3 starts the learn mode on the first parameter: the next control
+//! change or note received is mapped to it. Pressing F3 again moves to the next
+//! parameter, and leaves the learn mode after the last one. Mappings are saved
+//! in `midi/<artwork name>.json` at the root of the project.
+use crate::params::Params;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+/// The MIDI messages used to control parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MidiMessage {
+    NoteOn {
+        channel: u8,
+        note: u8,
+        velocity: u8,
+    },
+    NoteOff {
+        channel: u8,
+        note: u8,
+    },
+    ControlChange {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+}
+
+impl MidiMessage {
+    /// Parse a raw MIDI message. Messages that cannot control a parameter
+    /// return `None`.
+    pub fn from_bytes(bytes: &[u8]) -> Option<MidiMessage> {
+        let (&status, data) = bytes.split_first()?;
+        let channel = status & 0x0f;
+        match (status >> 4, data) {
+            (0x8, [note, ..]) => Some(MidiMessage::NoteOff {
+                channel,
+                note: *note,
+            }),
+            // A note on with a null velocity is a note off.
+            (0x9, [note, 0, ..]) => Some(MidiMessage::NoteOff {
+                channel,
+                note: *note,
+            }),
+            (0x9, [note, velocity, ..]) => Some(MidiMessage::NoteOn {
+                channel,
+                note: *note,
+                velocity: *velocity,
+            }),
+            (0xb, [controller, value, ..]) => Some(MidiMessage::ControlChange {
+                channel,
+                controller: *controller,
+                value: *value,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn source(&self) -> MidiSource {
+        match *self {
+            MidiMessage::NoteOn { channel, note, .. } | MidiMessage::NoteOff { channel, note } => {
+                MidiSource::Note { channel, note }
+            }
+            MidiMessage::ControlChange {
+                channel,
+                controller,
+                ..
+            } => MidiSource::Control {
+                channel,
+                controller,
+            },
+        }
+    }
+
+    /// The value carried by the message, between 0 and 1. Notes use their
+    /// velocity, and go back to 0 when released.
+    pub fn value(&self) -> f32 {
+        match *self {
+            MidiMessage::NoteOn { velocity, .. } => velocity as f32 / 127.,
+            MidiMessage::NoteOff { .. } => 0.,
+            MidiMessage::ControlChange { value, .. } => value as f32 / 127.,
+        }
+    }
+}
+
+/// The control or note a parameter is mapped to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MidiSource {
+    Control { channel: u8, controller: u8 },
+    Note { channel: u8, note: u8 },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MidiMapping {
+    pub source: MidiSource,
+    pub param: String,
+}
+
+/// Routes MIDI messages to parameters, handles the learn mode and smooths the
+/// changes of values.
+pub struct MidiMap {
+    pub mappings: Vec<MidiMapping>,
+    /// Time constant of the smoothing, in seconds. A value of 0 applies the
+    /// MIDI values immediately.
+    pub smoothing: f32,
+    learning: Option<String>,
+    /// The normalized values the parameters are moving towards.
+    targets: BTreeMap<String, f32>,
+}
+
+impl MidiMap {
+    pub fn new(mappings: Vec<MidiMapping>) -> MidiMap {
+        MidiMap {
+            mappings,
+            smoothing: 0.05,
+            learning: None,
+            targets: BTreeMap::new(),
+        }
+    }
+
+    /// Load the mappings from a JSON file. A missing file gives an empty map.
+    pub fn load(path: &Path) -> io::Result<MidiMap> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(MidiMap::new(serde_json::from_str(&json)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(MidiMap::new(vec![])),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(&self.mappings)?)
+    }
+
+    /// The name of the parameter waiting for a control, if in learn mode.
+    pub fn learning(&self) -> Option<&str> {
+        self.learning.as_deref()
+    }
+
+    /// Start the learn mode on the first parameter, or move it to the next
+    /// one. Returns `None` once the learn mode is over.
+    pub fn learn_next(&mut self, params: &Params) -> Option<&str> {
+        let mut names = params.iter().map(|p| &p.name);
+        self.learning = match &self.learning {
+            None => names.next().cloned(),
+            Some(current) => names.skip_while(|n| *n != current).nth(1).cloned(),
+        };
+        self.learning()
+    }
+
+    /// Handle one message. Returns `true` when a new mapping was learned.
+    pub fn handle(&mut self, message: MidiMessage) -> bool {
+        let source = message.source();
+        if let Some(param) = &self.learning {
+            // Note off messages would immediately remap the note just learned.
+            if let MidiMessage::NoteOff { .. } = message {
+                return false;
+            }
+            self.mappings
+                .retain(|m| m.source != source && m.param != *param);
+            self.mappings.push(MidiMapping {
+                source,
+                param: param.clone(),
+            });
+            return true;
+        }
+        for mapping in self.mappings.iter().filter(|m| m.source == source) {
+            self.targets.insert(mapping.param.clone(), message.value());
+        }
+        false
+    }
+
+    /// Move the mapped parameters towards the values received, `dt` seconds
+    /// after the previous call.
+    pub fn apply(&mut self, params: &mut Params, dt: f32) {
+        let factor = if self.smoothing > 0. {
+            1. - (-dt / self.smoothing).exp()
+        } else {
+            1.
+        };
+        self.targets.retain(|name, target| {
+            let param = match params.find_mut(name) {
+                Some(param) => param,
+                None => return false,
+            };
+            let current = param.normalized();
+            let next = current + (*target - current) * factor;
+            if (*target - next).abs() < 1e-4 {
+                param.set_normalized(*target);
+                // Once reached, the parameter is free to be changed by other
+                // means, e.g. the control panel.
+                false
+            } else {
+                param.set_normalized(next);
+                true
+            }
+        });
+    }
+}
+
+/// The messages of a standard MIDI file, with their time in seconds.
+pub struct MidiFile {
+    events: Vec<(f64, MidiMessage)>,
+}
+
+impl MidiFile {
+    pub fn load(path: &Path) -> io::Result<MidiFile> {
+        MidiFile::parse(&fs::read(path)?)
+    }
+
+    pub fn parse(bytes: &[u8]) -> io::Result<MidiFile> {
+        use midly::{MetaMessage, MidiMessage as SmfMessage, Smf, Timing, TrackEventKind};
+
+        let smf = Smf::parse(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        // Merge the tracks, keeping the absolute time in ticks.
+        let mut events = vec![];
+        for track in smf.tracks.iter() {
+            let mut tick = 0u64;
+            for event in track {
+                tick += event.delta.as_int() as u64;
+                events.push((tick, event.kind));
+            }
+        }
+        events.sort_by_key(|(tick, _)| *tick);
+
+        // Convert the ticks to seconds, following the tempo changes.
+        let mut seconds_per_tick = match smf.header.timing {
+            // The default tempo is 120 beats per minute.
+            Timing::Metrical(ticks_per_beat) => 0.5 / ticks_per_beat.as_int() as f64,
+            Timing::Timecode(fps, subframes) => 1. / (fps.as_f32() as f64 * subframes as f64),
+        };
+        let (mut last_tick, mut time) = (0, 0.);
+        let mut messages = vec![];
+        for (tick, kind) in events {
+            time += (tick - last_tick) as f64 * seconds_per_tick;
+            last_tick = tick;
+            match kind {
+                TrackEventKind::Meta(MetaMessage::Tempo(micros_per_beat)) => {
+                    if let Timing::Metrical(ticks_per_beat) = smf.header.timing {
+                        seconds_per_tick =
+                            micros_per_beat.as_int() as f64 / 1e6 / ticks_per_beat.as_int() as f64;
+                    }
+                }
+                TrackEventKind::Midi { channel, message } => {
+                    let channel = channel.as_int();
+                    let message = match message {
+                        SmfMessage::NoteOn { key, vel } if vel > 0 => MidiMessage::NoteOn {
+                            channel,
+                            note: key.as_int(),
+                            velocity: vel.as_int(),
+                        },
+                        SmfMessage::NoteOn { key, .. } | SmfMessage::NoteOff { key, .. } => {
+                            MidiMessage::NoteOff {
+                                channel,
+                                note: key.as_int(),
+                            }
+                        }
+                        SmfMessage::Controller { controller, value } => {
+                            MidiMessage::ControlChange {
+                                channel,
+                                controller: controller.as_int(),
+                                value: value.as_int(),
+                            }
+                        }
+                        _ => continue,
+                    };
+                    messages.push((time, message));
+                }
+                _ => {}
+            }
+        }
+        Ok(MidiFile { events: messages })
+    }
+
+    /// The time of the last message, in seconds.
+    pub fn duration(&self) -> f64 {
+        self.events.last().map_or(0., |(time, _)| *time)
+    }
+
+    /// The messages sent in the `start..end` time range, in seconds.
+    pub fn messages_between(&self, start: f64, end: f64) -> impl Iterator<Item = MidiMessage> + '_ {
+        self.events
+            .iter()
+            .skip_while(move |(time, _)| *time < start)
+            .take_while(move |(time, _)| *time < end)
+            .map(|(_, message)| *message)
+    }
+}
+
+enum Input {
+    Device {
+        // The connection is closed when dropped.
+        _connection: midir::MidiInputConnection<()>,
+        receiver: mpsc::Receiver<MidiMessage>,
+    },
+    /// A MIDI file played in a loop, standing in for a controller.
+    File { file: MidiFile, position: f64 },
+}
+
+/// A MIDI input together with the mapping of its controls to parameters.
+pub struct Midi {
+    input: Input,
+    pub map: MidiMap,
+    mapping_path: PathBuf,
+}
+
+impl Midi {
+    /// Connect to the first input port whose name contains `port`. The name
+    /// `virtual` creates a virtual port called "artworks" instead, to which
+    /// other applications can connect.
+    pub fn connect(port: &str, mapping_path: PathBuf) -> Result<Midi, Box<dyn Error>> {
+        let input = midir::MidiInput::new("artworks")?;
+        let (sender, receiver) = mpsc::channel();
+        let callback = move |_stamp: u64, bytes: &[u8], _: &mut ()| {
+            if let Some(message) = MidiMessage::from_bytes(bytes) {
+                // The receiver only goes away when the artwork exits.
+                sender.send(message).ok();
+            }
+        };
+        let connection = if port == "virtual" {
+            virtual_port(input, callback)?
+        } else {
+            let input_port = input
+                .ports()
+                .into_iter()
+                .find(|p| input.port_name(p).map_or(false, |name| name.contains(port)))
+                .ok_or_else(|| format!("no MIDI input port matching {}", port))?;
+            input.connect(&input_port, "artworks-in", callback, ())?
+        };
+        Ok(Midi {
+            input: Input::Device {
+                _connection: connection,
+                receiver,
+            },
+            map: MidiMap::load(&mapping_path)?,
+            mapping_path,
+        })
+    }
+
+    /// Replay the messages of a MIDI file in a loop.
+    pub fn from_file(path: &Path, mapping_path: PathBuf) -> io::Result<Midi> {
+        Ok(Midi {
+            input: Input::File {
+                file: MidiFile::load(path)?,
+                position: 0.,
+            },
+            map: MidiMap::load(&mapping_path)?,
+            mapping_path,
+        })
+    }
+
+    /// Open the MIDI input given with `--midi PORT` or `--midi-file PATH` on
+    /// the command line, if any.
+    pub fn from_args(mapping_path: PathBuf) -> Option<Midi> {
+        let args: Vec<String> = std::env::args().collect();
+        let value = |flag: &str| {
+            args.iter()
+                .position(|a| a == flag)
+                .and_then(|i| args.get(i + 1))
+        };
+        let midi = if let Some(port) = value("--midi") {
+            Midi::connect(port, mapping_path)
+        } else if let Some(path) = value("--midi-file") {
+            Midi::from_file(Path::new(path), mapping_path).map_err(|e| e.into())
+        } else {
+            return None;
+        };
+        midi.map_err(|e| eprintln!("Could not open MIDI input: {}", e))
+            .ok()
+    }
+
+    /// Handle the messages received since the last update, `dt` seconds ago.
+    pub fn update(&mut self, params: &mut Params, dt: f32) {
+        let messages: Vec<MidiMessage> = match &mut self.input {
+            Input::Device { receiver, .. } => receiver.try_iter().collect(),
+            Input::File { file, position } => {
+                let start = *position;
+                *position += dt as f64;
+                let messages = file.messages_between(start, *position).collect();
+                if *position > file.duration() {
+                    *position = 0.;
+                }
+                messages
+            }
+        };
+        let mut learned = false;
+        for message in messages {
+            learned |= self.map.handle(message);
+        }
+        if learned {
+            if let Some(param) = self.map.learning() {
+                println!("Mapped MIDI control to {}", param);
+            }
+            if let Err(e) = self.map.save(&self.mapping_path) {
+                eprintln!("Could not save MIDI mappings: {}", e);
+            }
+        }
+        self.map.apply(params, dt);
+    }
+
+    /// Start or advance the learn mode, see [`MidiMap::learn_next`].
+    pub fn learn_next(&mut self, params: &Params) {
+        match self.map.learn_next(params) {
+            Some(param) => println!("MIDI learn: move a control to map it to {}", param),
+            None => println!("MIDI learn done"),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn virtual_port<F>(
+    input: midir::MidiInput,
+    callback: F,
+) -> Result<midir::MidiInputConnection<()>, Box<dyn Error>>
+where
+    F: FnMut(u64, &[u8], &mut ()) + Send + 'static,
+{
+    use midir::os::unix::VirtualInput;
+    Ok(input.create_virtual("artworks", callback, ())?)
+}
+
+#[cfg(not(unix))]
+fn virtual_port<F>(
+    _input: midir::MidiInput,
+    _callback: F,
+) -> Result<midir::MidiInputConnection<()>, Box<dyn Error>>
+where
+    F: FnMut(u64, &[u8], &mut ()) + Send + 'static,
+{
+    Err("virtual MIDI ports are not supported on this platform".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MidiFile, MidiMap, MidiMessage, MidiSource};
+    use crate::params::Params;
+
+    #[test]
+    fn parse_raw_messages() {
+        assert_eq!(
+            MidiMessage::from_bytes(&[0xb2, 7, 127]),
+            Some(MidiMessage::ControlChange {
+                channel: 2,
+                controller: 7,
+                value: 127
+            })
+        );
+        assert_eq!(
+            MidiMessage::from_bytes(&[0x90, 60, 0]),
+            Some(MidiMessage::NoteOff {
+                channel: 0,
+                note: 60
+            })
+        );
+        assert_eq!(MidiMessage::from_bytes(&[0xf8]), None);
+    }
+
+    #[test]
+    fn learn_and_smooth() {
+        let mut params = Params::default();
+        params.register("size", 0., 0., 10.);
+        params.register("speed", 0., 0., 1.);
+        let mut map = MidiMap::new(vec![]);
+        map.smoothing = 0.;
+
+        assert_eq!(map.learn_next(&params), Some("size"));
+        assert!(map.handle(MidiMessage::from_bytes(&[0xb0, 1, 0]).unwrap()));
+        assert_eq!(map.learn_next(&params), Some("speed"));
+        assert!(map.handle(MidiMessage::from_bytes(&[0x90, 36, 100]).unwrap()));
+        assert_eq!(map.learn_next(&params), None);
+        assert_eq!(
+            map.mappings[0].source,
+            MidiSource::Control {
+                channel: 0,
+                controller: 1
+            }
+        );
+
+        map.handle(MidiMessage::from_bytes(&[0xb0, 1, 127]).unwrap());
+        map.apply(&mut params, 1. / 60.);
+        assert_eq!(params.get("size"), 10.);
+
+        map.smoothing = 0.1;
+        map.handle(MidiMessage::from_bytes(&[0x90, 36, 127]).unwrap());
+        map.apply(&mut params, 1. / 60.);
+        let speed = params.get("speed");
+        assert!(speed > 0. && speed < 1.);
+        map.apply(&mut params, 10.);
+        assert_eq!(params.get("speed"), 1.);
+    }
+
+    #[test]
+    fn read_midi_file() {
+        // One track at 96 ticks per beat, with a control change at 0 and
+        // another one a beat later.
+        #[rustfmt::skip]
+        let bytes = [
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96,
+            b'M', b'T', b'r', b'k', 0, 0, 0, 12,
+            0, 0xb0, 7, 100,
+            96, 0xb0, 7, 0,
+            0, 0xff, 0x2f, 0,
+        ];
+        let file = MidiFile::parse(&bytes).unwrap();
+        assert_eq!(file.duration(), 0.5);
+        assert_eq!(file.messages_between(0., 0.25).count(), 1);
+        assert_eq!(
+            file.messages_between(0.25, 1.).next(),
+            Some(MidiMessage::ControlChange {
+                channel: 0,
+                controller: 7,
+                value: 0
+            })
+        );
+    }
+}
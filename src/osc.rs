@@ -0,0 +1,218 @@
+//! Remote control of an artwork with OSC.
+//!
+//! The server is enabled by giving the UDP port to listen to on the command
+//! line:
+//!
+//! ``` sh
+//! cargo run --release --example flowfield -- --osc 9000
+//! ```
+//!
+//! The following addresses are understood:
+//!
+//! | Address                      | Arguments | Action                                 |
+//! |------------------------------|-----------|----------------------------------------|
+//! | `/param/<name>`              | value     | Set a parameter                        |
+//! | `/param/<name>/normalized`   | 0..1      | Set a parameter relative to its range  |
+//! | `/seed`                      | seed      | Set the seed                           |
+//! | `/seed/random`               |           | Pick a new random seed                 |
+//! | `/record`                    | 0 or 1    | Stop or start recording                |
+//! | `/record/start`              |           | Start recording                        |
+//! | `/record/stop`               |           | Stop recording                         |
+//! | `/time`                      | 0..1      | Move to a time in the animation        |
+//! | `/frame`                     | frame     | Move to a frame of the animation       |
+//!
+//! Numerical arguments can be sent as ints, floats, doubles or longs.
+use crate::{set_recording, BaseModel};
+use nannou::prelude::random;
+use nannou_osc as osc;
+
+/// A command received over OSC.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OscCommand {
+    SetParam { name: String, value: f32 },
+    SetParamNormalized { name: String, value: f32 },
+    Seed(i32),
+    RandomSeed,
+    Record(bool),
+    Time(f64),
+    Frame(u32),
+}
+
+impl OscCommand {
+    /// Parse an address and its numerical arguments. Unknown addresses and
+    /// missing arguments return `None`.
+    pub fn parse(addr: &str, args: &[f64]) -> Option<OscCommand> {
+        let first = args.first().copied();
+        let parts: Vec<&str> = addr.trim_start_matches('/').split('/').collect();
+        match parts.as_slice() {
+            ["param", name] => Some(OscCommand::SetParam {
+                name: name.to_string(),
+                value: first? as f32,
+            }),
+            ["param", name, "normalized"] => Some(OscCommand::SetParamNormalized {
+                name: name.to_string(),
+                value: first? as f32,
+            }),
+            ["seed"] => Some(OscCommand::Seed(first? as i32)),
+            ["seed", "random"] => Some(OscCommand::RandomSeed),
+            ["record"] => Some(OscCommand::Record(first? != 0.)),
+            ["record", "start"] => Some(OscCommand::Record(true)),
+            ["record", "stop"] => Some(OscCommand::Record(false)),
+            ["time"] => Some(OscCommand::Time(first?)),
+            ["frame"] => Some(OscCommand::Frame(first?.max(0.) as u32)),
+            _ => None,
+        }
+    }
+}
+
+/// Listens to OSC packets on a UDP port.
+pub struct OscServer {
+    receiver: osc::Receiver,
+}
+
+impl OscServer {
+    pub fn bind(port: u16) -> std::io::Result<OscServer> {
+        Ok(OscServer {
+            receiver: osc::receiver(port)?,
+        })
+    }
+
+    /// The address the server listens on, e.g. to find the port picked when
+    /// binding port 0.
+    pub fn local_addr(&self) -> std::io::Result<std::net::SocketAddr> {
+        self.receiver.local_addr()
+    }
+
+    /// Start the server on the port given with `--osc PORT` on the command
+    /// line, if any.
+    pub fn from_args() -> Option<OscServer> {
+        let mut args = std::env::args().skip_while(|a| a != "--osc");
+        args.next();
+        let port = args.next()?;
+        let server = port
+            .parse()
+            .map_err(|_| format!("invalid port {}", port))
+            .and_then(|port| OscServer::bind(port).map_err(|e| e.to_string()));
+        match server {
+            Ok(server) => {
+                println!("Listening to OSC messages on port {}", port);
+                Some(server)
+            }
+            Err(e) => {
+                eprintln!("Could not start the OSC server: {}", e);
+                None
+            }
+        }
+    }
+
+    /// The commands received since the last call.
+    pub fn commands(&self) -> Vec<OscCommand> {
+        let mut commands = vec![];
+        for (packet, _addr) in self.receiver.try_iter() {
+            for message in packet.into_msgs() {
+                let args: Vec<f64> = message
+                    .args
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|arg| match arg {
+                        osc::Type::Int(v) => Some(v as f64),
+                        osc::Type::Long(v) => Some(v as f64),
+                        osc::Type::Float(v) => Some(v as f64),
+                        osc::Type::Double(v) => Some(v),
+                        osc::Type::Bool(v) => Some(v as u8 as f64),
+                        _ => None,
+                    })
+                    .collect();
+                match OscCommand::parse(&message.addr, &args) {
+                    Some(command) => commands.push(command),
+                    None => eprintln!("Ignoring OSC message {}", message.addr),
+                }
+            }
+        }
+        commands
+    }
+}
+
+/// Apply a command to the running artwork, which has `n_frames` frames.
+pub(crate) fn apply(base_model: &mut BaseModel, command: OscCommand, n_frames: u32) {
+    match command {
+        OscCommand::SetParam { name, value } => {
+            if !base_model.params.set(&name, value) {
+                eprintln!("Unknown parameter {}", name);
+            }
+        }
+        OscCommand::SetParamNormalized { name, value } => match base_model.params.find_mut(&name) {
+            Some(param) => param.set_normalized(value),
+            None => eprintln!("Unknown parameter {}", name),
+        },
+        OscCommand::Seed(seed) => base_model.seed = seed,
+        OscCommand::RandomSeed => base_model.seed = random(),
+        OscCommand::Record(recording) => set_recording(base_model, recording),
+        OscCommand::Time(time) => {
+            let frame = (time.rem_euclid(1.) * n_frames as f64) as u32;
+            base_model.transport.jump(frame, n_frames);
+        }
+        OscCommand::Frame(frame) => base_model.transport.jump(frame, n_frames),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OscCommand, OscServer};
+    use nannou_osc as osc;
+
+    #[test]
+    fn parse_addresses() {
+        assert_eq!(
+            OscCommand::parse("/param/speed", &[0.5]),
+            Some(OscCommand::SetParam {
+                name: "speed".to_string(),
+                value: 0.5
+            })
+        );
+        assert_eq!(
+            OscCommand::parse("/param/speed/normalized", &[1.]),
+            Some(OscCommand::SetParamNormalized {
+                name: "speed".to_string(),
+                value: 1.
+            })
+        );
+        assert_eq!(
+            OscCommand::parse("/record", &[1.]),
+            Some(OscCommand::Record(true))
+        );
+        assert_eq!(
+            OscCommand::parse("/record/stop", &[]),
+            Some(OscCommand::Record(false))
+        );
+        assert_eq!(
+            OscCommand::parse("/frame", &[12.]),
+            Some(OscCommand::Frame(12))
+        );
+        assert_eq!(OscCommand::parse("/seed", &[]), None);
+        assert_eq!(OscCommand::parse("/unknown", &[1.]), None);
+    }
+
+    #[test]
+    fn receive_from_local_client() {
+        // Port 0 lets the system pick a free port.
+        let server = OscServer::bind(0).unwrap();
+        let port = server.local_addr().unwrap().port();
+        let sender = osc::sender().unwrap().connect(("127.0.0.1", port)).unwrap();
+        sender
+            .send(("/param/speed", vec![osc::Type::Float(0.25)]))
+            .unwrap();
+        sender.send(("/seed/random", vec![])).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert_eq!(
+            server.commands(),
+            vec![
+                OscCommand::SetParam {
+                    name: "speed".to_string(),
+                    value: 0.25
+                },
+                OscCommand::RandomSeed
+            ]
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Named numerical parameters exposed by an artwork.
+//!
+//! An artwork registers its parameters once in [`Artwork::new`], and then
+//! reads their current value in [`Artwork::draw_at_time`]. Because the
+//! recorder knows about them, they can be saved in presets and tweaked while
+//! the animation is running.
+//!
+//! ```
+//! use artworks::params::Params;
+//!
+//! let mut params = Params::default();
+//! params.register("speed", 1., 0., 4.);
+//! params.set("speed", 2.5);
+//! assert_eq!(params.get("speed"), 2.5);
+//! ```
+//!
+//! [`Artwork::new`]: crate::Artwork::new
+//! [`Artwork::draw_at_time`]: crate::Artwork::draw_at_time
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A single parameter, with its current value and the range it can take.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Param {
+    pub name: String,
+    pub value: f32,
+    pub default: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Param {
+    /// The current value mapped to the 0..1 range.
+    pub fn normalized(&self) -> f32 {
+        if self.max > self.min {
+            (self.value - self.min) / (self.max - self.min)
+        } else {
+            0.
+        }
+    }
+
+    /// Set the value from a 0..1 position in the range of the parameter.
+    pub fn set_normalized(&mut self, position: f32) {
+        self.value = self.min + position.clamp(0., 1.) * (self.max - self.min);
+    }
+}
+
+/// The collection of parameters registered by an artwork. Parameters are kept
+/// in registration order so that user interfaces list them predictably.
+#[derive(Clone, Debug, Default)]
+pub struct Params {
+    params: Vec<Param>,
+}
+
+impl Params {
+    /// Register a new parameter and return its default value. Registering a
+    /// name twice only updates its range and keeps the current value.
+    pub fn register(&mut self, name: &str, default: f32, min: f32, max: f32) -> f32 {
+        if let Some(param) = self.find_mut(name) {
+            param.min = min;
+            param.max = max;
+            param.default = default;
+            param.value = param.value.clamp(min, max);
+            return param.value;
+        }
+        self.params.push(Param {
+            name: name.to_string(),
+            value: default,
+            default,
+            min,
+            max,
+        });
+        default
+    }
+
+    /// Get the current value of a parameter.
+    ///
+    /// Panics if the parameter was never registered, since this is always a
+    /// typo in the artwork.
+    pub fn get(&self, name: &str) -> f32 {
+        self.find(name)
+            .unwrap_or_else(|| panic!("unknown parameter `{}`", name))
+            .value
+    }
+
+    /// Set the value of a parameter, clamped to its range. Returns `false` if
+    /// the parameter does not exist.
+    pub fn set(&mut self, name: &str, value: f32) -> bool {
+        match self.find_mut(name) {
+            Some(param) => {
+                param.value = value.clamp(param.min, param.max);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reset every parameter to its default value.
+    pub fn reset(&mut self) {
+        for param in self.params.iter_mut() {
+            param.value = param.default;
+        }
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Param> {
+        self.params.iter().find(|p| p.name == name)
+    }
+
+    pub fn find_mut(&mut self, name: &str) -> Option<&mut Param> {
+        self.params.iter_mut().find(|p| p.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Param> {
+        self.params.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Param> {
+        self.params.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    /// A snapshot of the current values, keyed by name.
+    pub fn values(&self) -> BTreeMap<String, f32> {
+        self.params
+            .iter()
+            .map(|p| (p.name.clone(), p.value))
+            .collect()
+    }
+
+    /// Set several values at once. Unknown names are ignored so that presets
+    /// saved with an older version of an artwork can still be loaded.
+    pub fn set_values(&mut self, values: &BTreeMap<String, f32>) {
+        for (name, value) in values {
+            self.set(name, *value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Params;
+
+    #[test]
+    fn register_and_set_are_clamped() {
+        let mut params = Params::default();
+        assert_eq!(params.register("speed", 1., 0., 2.), 1.);
+        assert!(params.set("speed", 5.));
+        assert_eq!(params.get("speed"), 2.);
+        assert!(!params.set("unknown", 1.));
+    }
+
+    #[test]
+    fn values_roundtrip() {
+        let mut params = Params::default();
+        params.register("a", 0.5, 0., 1.);
+        params.register("b", 3., 0., 10.);
+        let mut values = params.values();
+        values.insert("a".to_string(), 0.25);
+        values.insert("removed".to_string(), 1.);
+        params.set_values(&values);
+        assert_eq!(params.get("a"), 0.25);
+        assert_eq!(params.get("b"), 3.);
+        params.reset();
+        assert_eq!(params.get("a"), 0.5);
+    }
+}
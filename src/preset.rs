@@ -0,0 +1,188 @@
+//! Save and load the configuration of an artwork.
+//!
+//! A [`Preset`] holds the seed, the values of the registered
+//! [parameters](crate::params) and the [`Options`] of an artwork. Presets are
+//! stored as JSON files in `presets/<artwork name>/` at the root of the
+//! project, and can be loaded back with a key press or from the command line:
+//!
+//! ``` sh
+//! cargo run --release --example flowfield -- --preset preset-003
+//! ```
+use crate::Options;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+const EXTENSION: &str = "json";
+
+/// A saved configuration of an artwork.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Preset {
+    pub seed: i32,
+    #[serde(default)]
+    pub params: BTreeMap<String, f32>,
+    #[serde(default)]
+    pub options: Options,
+}
+
+impl Preset {
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Preset> {
+        let json = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+/// The presets available on disk for one artwork, with a cursor used to cycle
+/// through them in the preview window.
+pub struct PresetLibrary {
+    directory: PathBuf,
+    names: Vec<String>,
+    current: Option<usize>,
+}
+
+impl PresetLibrary {
+    /// List the presets stored in `directory`. The directory is only created
+    /// when the first preset is saved.
+    pub fn new(directory: PathBuf) -> PresetLibrary {
+        let mut library = PresetLibrary {
+            directory,
+            names: vec![],
+            current: None,
+        };
+        library.refresh();
+        library
+    }
+
+    /// Scan the preset directory again, e.g. after files were added by hand.
+    pub fn refresh(&mut self) {
+        let current = self.current_name().map(str::to_string);
+        self.names = fs::read_dir(&self.directory)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().map_or(false, |ext| ext == EXTENSION))
+                    .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.names.sort();
+        self.current = current.and_then(|name| self.names.iter().position(|n| *n == name));
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn current_name(&self) -> Option<&str> {
+        self.current.map(|i| self.names[i].as_str())
+    }
+
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.directory.join(name).with_extension(EXTENSION)
+    }
+
+    /// Save `preset` under `name`, overwriting any preset with the same name.
+    pub fn save(&mut self, name: &str, preset: &Preset) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.directory)?;
+        let path = self.path(name);
+        preset.save(&path)?;
+        if !self.names.iter().any(|n| n == name) {
+            self.names.push(name.to_string());
+            self.names.sort();
+        }
+        self.current = self.names.iter().position(|n| n == name);
+        Ok(path)
+    }
+
+    /// Save `preset` under the first free `preset-NNN` name.
+    pub fn save_new(&mut self, preset: &Preset) -> io::Result<PathBuf> {
+        let name = (0..)
+            .map(|i| format!("preset-{:03}", i))
+            .find(|n| !self.names.contains(n))
+            .unwrap();
+        self.save(&name, preset)
+    }
+
+    pub fn load(&mut self, name: &str) -> io::Result<Preset> {
+        let preset = Preset::load(&self.path(name))?;
+        self.current = self.names.iter().position(|n| n == name);
+        Ok(preset)
+    }
+
+    /// Load the preset after the current one, wrapping around.
+    pub fn next(&mut self) -> Option<io::Result<(String, Preset)>> {
+        self.step(1)
+    }
+
+    /// Load the preset before the current one, wrapping around.
+    pub fn previous(&mut self) -> Option<io::Result<(String, Preset)>> {
+        self.step(-1)
+    }
+
+    fn step(&mut self, offset: isize) -> Option<io::Result<(String, Preset)>> {
+        if self.names.is_empty() {
+            return None;
+        }
+        let n = self.names.len() as isize;
+        let index = match self.current {
+            Some(i) => (i as isize + offset).rem_euclid(n),
+            None if offset < 0 => n - 1,
+            None => 0,
+        } as usize;
+        let name = self.names[index].clone();
+        Some(self.load(&name).map(|preset| (name, preset)))
+    }
+}
+
+/// Read the preset name given with `--preset NAME` on the command line.
+pub fn preset_from_args() -> Option<String> {
+    let mut args = std::env::args().skip_while(|a| a != "--preset");
+    args.next();
+    args.next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Preset, PresetLibrary};
+    use crate::Options;
+
+    #[test]
+    fn cycle_through_saved_presets() {
+        let directory =
+            std::env::temp_dir().join(format!("artworks-presets-{}", std::process::id()));
+        let mut library = PresetLibrary::new(directory.clone());
+        assert!(library.next().is_none());
+
+        for seed in 0..3 {
+            let mut preset = Preset {
+                seed,
+                params: Default::default(),
+                options: Options::default(),
+            };
+            preset.params.insert("speed".to_string(), seed as f32);
+            library.save_new(&preset).unwrap();
+        }
+        assert_eq!(library.current_name(), Some("preset-002"));
+
+        let (name, preset) = library.next().unwrap().unwrap();
+        assert_eq!(name, "preset-000");
+        assert_eq!(preset.seed, 0);
+        let (name, preset) = library.previous().unwrap().unwrap();
+        assert_eq!(name, "preset-002");
+        assert_eq!(preset.params["speed"], 2.);
+
+        let mut reloaded = PresetLibrary::new(directory.clone());
+        assert_eq!(reloaded.names().len(), 3);
+        assert_eq!(reloaded.load("preset-001").unwrap().seed, 1);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,186 @@
+//! Browse the seeds tried during a session, and bookmark the good ones.
+//!
+//! Every seed used in the preview window is kept in a [`SeedHistory`], which
+//! can be browsed with PageUp and PageDown. F8 adds the current seed to the
+//! [`Bookmarks`] of the artwork, stored with a thumbnail of the current frame
+//! in `bookmarks/<artwork name>/` at the root of the project.
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf};
+
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+/// The seeds used in the session, in order.
+pub struct SeedHistory {
+    seeds: Vec<i32>,
+    position: usize,
+}
+
+impl SeedHistory {
+    pub fn new(seed: i32) -> SeedHistory {
+        SeedHistory {
+            seeds: vec![seed],
+            position: 0,
+        }
+    }
+
+    pub fn current(&self) -> i32 {
+        self.seeds[self.position]
+    }
+
+    /// Record the seed in use. A seed different from the current one is added
+    /// at the end of the history, so browsing back never loses a seed.
+    pub fn record(&mut self, seed: i32) {
+        if seed != self.current() {
+            self.seeds.push(seed);
+            self.position = self.seeds.len() - 1;
+        }
+    }
+
+    /// Move to the previous seed, if any.
+    pub fn previous(&mut self) -> Option<i32> {
+        self.position = self.position.checked_sub(1)?;
+        Some(self.current())
+    }
+
+    /// Move to the next seed, if any.
+    pub fn next(&mut self) -> Option<i32> {
+        if self.position + 1 >= self.seeds.len() {
+            return None;
+        }
+        self.position += 1;
+        Some(self.current())
+    }
+
+    pub fn seeds(&self) -> &[i32] {
+        &self.seeds
+    }
+}
+
+/// A favourite seed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub seed: i32,
+    /// The frame shown in the thumbnail.
+    pub frame: u32,
+}
+
+/// The bookmarked seeds of one artwork.
+pub struct Bookmarks {
+    directory: PathBuf,
+    bookmarks: Vec<Bookmark>,
+}
+
+impl Bookmarks {
+    /// Load the bookmarks stored in `directory`, if any. The directory is only
+    /// created when the first seed is bookmarked.
+    pub fn load(directory: PathBuf) -> io::Result<Bookmarks> {
+        let bookmarks = match fs::read_to_string(directory.join(BOOKMARKS_FILE)) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e),
+        };
+        Ok(Bookmarks {
+            directory,
+            bookmarks,
+        })
+    }
+
+    /// Empty bookmarks, stored in `directory` once a seed is added.
+    pub fn empty(directory: PathBuf) -> Bookmarks {
+        Bookmarks {
+            directory,
+            bookmarks: vec![],
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Bookmark> {
+        self.bookmarks.iter()
+    }
+
+    pub fn contains(&self, seed: i32) -> bool {
+        self.bookmarks.iter().any(|b| b.seed == seed)
+    }
+
+    /// Bookmark `seed` as shown at `frame`, replacing any bookmark of the same
+    /// seed, and return the path where its thumbnail should be saved.
+    pub fn add(&mut self, seed: i32, frame: u32) -> io::Result<PathBuf> {
+        self.bookmarks.retain(|b| b.seed != seed);
+        self.bookmarks.push(Bookmark { seed, frame });
+        self.save()?;
+        Ok(self.thumbnail_path(seed))
+    }
+
+    pub fn remove(&mut self, seed: i32) -> io::Result<()> {
+        self.bookmarks.retain(|b| b.seed != seed);
+        self.save()?;
+        match fs::remove_file(self.thumbnail_path(seed)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn thumbnail_path(&self, seed: i32) -> PathBuf {
+        self.directory.join(seed.to_string()).with_extension("png")
+    }
+
+    fn save(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.directory)?;
+        let json = serde_json::to_string_pretty(&self.bookmarks)?;
+        fs::write(self.directory.join(BOOKMARKS_FILE), json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bookmark, Bookmarks, SeedHistory};
+    use std::fs;
+
+    #[test]
+    fn browse_history() {
+        let mut history = SeedHistory::new(1);
+        assert_eq!(history.previous(), None);
+        history.record(2);
+        history.record(2);
+        history.record(3);
+        assert_eq!(history.seeds(), &[1, 2, 3]);
+        assert_eq!(history.previous(), Some(2));
+        assert_eq!(history.previous(), Some(1));
+        // Recording the seed we browsed to keeps the history as it is.
+        history.record(1);
+        assert_eq!(history.next(), Some(2));
+        // A new seed goes at the end.
+        history.record(4);
+        assert_eq!(history.seeds(), &[1, 2, 3, 4]);
+        assert_eq!(history.next(), None);
+        assert_eq!(history.previous(), Some(3));
+    }
+
+    #[test]
+    fn bookmarks_survive_restarts() {
+        let directory =
+            std::env::temp_dir().join(format!("artworks-bookmarks-test-{}", std::process::id()));
+        fs::remove_dir_all(&directory).ok();
+
+        let mut bookmarks = Bookmarks::load(directory.clone()).unwrap();
+        assert_eq!(bookmarks.iter().count(), 0);
+        let thumbnail = bookmarks.add(42, 10).unwrap();
+        assert_eq!(thumbnail, directory.join("42.png"));
+        bookmarks.add(7, 0).unwrap();
+        bookmarks.add(42, 20).unwrap();
+
+        let mut bookmarks = Bookmarks::load(directory.clone()).unwrap();
+        assert_eq!(
+            bookmarks.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                Bookmark { seed: 7, frame: 0 },
+                Bookmark {
+                    seed: 42,
+                    frame: 20
+                }
+            ]
+        );
+        bookmarks.remove(7).unwrap();
+        assert!(!Bookmarks::load(directory.clone()).unwrap().contains(7));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,91 @@
+struct FragmentOutput {
+    [[location(0)]] out_color: vec4<f32>;
+};
+
+[[block]]
+struct Layer {
+    blend: i32;
+    opacity: f32;
+    brightness: f32;
+    contrast: f32;
+    saturation: f32;
+    // 0 without a mask, 1 to show the layer inside the mask, 2 outside.
+    clip: i32;
+    stencil: i32;
+};
+
+// The composite of the layers below, and the layer to blend over it.
+[[group(0), binding(0)]]
+var below: texture_multisampled_2d<f32>;
+[[group(0), binding(1)]]
+var layer: texture_multisampled_2d<f32>;
+[[group(0), binding(2)]]
+var mask: texture_multisampled_2d<f32>;
+[[group(0), binding(3)]]
+var<uniform> uniforms: Layer;
+
+[[stage(fragment)]]
+fn main(
+    [[location(0)]] tex_coords: vec2<f32>,
+) -> FragmentOutput {
+    let tex_size: vec2<i32> = textureDimensions(below);
+    let p: vec2<i32> = vec2<i32>(
+        i32(f32(tex_size.x) * tex_coords.x),
+        i32(f32(tex_size.y) * tex_coords.y),
+    );
+
+    // Resolve the samples, as in the main fragment shader. The colors are
+    // premultiplied by their alpha.
+    let b: vec4<f32> = 0.25 * (
+        textureLoad(below, p, 0) + textureLoad(below, p, 1)
+        + textureLoad(below, p, 2) + textureLoad(below, p, 3)
+    );
+    var s: vec4<f32> = 0.25 * (
+        textureLoad(layer, p, 0) + textureLoad(layer, p, 1)
+        + textureLoad(layer, p, 2) + textureLoad(layer, p, 3)
+    );
+
+    // The effects of the layer apply to its straight colors.
+    var cs: vec3<f32> = s.rgb / max(s.a, 0.00001);
+    cs = cs + uniforms.brightness;
+    cs = (cs - 0.5) * uniforms.contrast + 0.5;
+    let luma: f32 = dot(cs, vec3<f32>(0.2126, 0.7152, 0.0722));
+    cs = mix(vec3<f32>(luma, luma, luma), cs, uniforms.saturation);
+
+    // The alpha of the mask is its coverage, or is cut to 0 or 1 for stencils.
+    var coverage: f32 = 1.0;
+    if (uniforms.clip != 0) {
+        coverage = 0.25 * (
+            textureLoad(mask, p, 0).a + textureLoad(mask, p, 1).a
+            + textureLoad(mask, p, 2).a + textureLoad(mask, p, 3).a
+        );
+        if (uniforms.stencil != 0) {
+            coverage = step(0.5, coverage);
+        }
+        if (uniforms.clip == 2) {
+            coverage = 1.0 - coverage;
+        }
+    }
+    let a_s: f32 = s.a * uniforms.opacity * coverage;
+
+    let cb: vec3<f32> = b.rgb / max(b.a, 0.00001);
+    var mixed: vec3<f32> = cs;
+    if (uniforms.blend == 1) {
+        mixed = cb * cs;
+    } elseif (uniforms.blend == 2) {
+        mixed = cb + cs - cb * cs;
+    } elseif (uniforms.blend == 3) {
+        mixed = cb + cs;
+    } elseif (uniforms.blend == 4) {
+        let low: vec3<f32> = 2.0 * cs * cb;
+        let high: vec3<f32> = 1.0 - 2.0 * (1.0 - cs) * (1.0 - cb);
+        mixed = mix(low, high, step(vec3<f32>(0.5, 0.5, 0.5), cb));
+    } elseif (uniforms.blend == 5) {
+        mixed = abs(cb - cs);
+    }
+
+    // Separable blending of the W3C compositing specification.
+    let color: vec3<f32> = cs * a_s * (1.0 - b.a) + b.rgb * (1.0 - a_s) + mixed * a_s * b.a;
+    let alpha: f32 = a_s + b.a * (1.0 - a_s);
+    return FragmentOutput(vec4<f32>(color, alpha));
+}
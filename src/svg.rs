@@ -0,0 +1,694 @@
+//! Export of the vector geometry of a frame to SVG.
+//!
+//! nannou's `Draw` keeps its primitives to itself, so the geometry to export
+//! is drawn with [`VectorDraw`], which draws to `base.draw` as usual and also
+//! records each primitive with its transform and colors. Pressing F9 writes what
+//! was recorded while rendering the current frame, in its last motion blur
+//! sample, to `svg/<artwork>/<seed>-<frame>.svg`, at the coordinates of the
+//! canvas.
+//!
+//! ```ignore
+//! fn draw_at_time(&mut self, time: f64) {
+//!     self.base.draw.reset();
+//!     let vector = &self.base.vector;
+//!     vector.background(BLACK);
+//!     let cell = vector.translate(vec3(100., 0., 0.)).rotate(time as f32);
+//!     cell.rect().w_h(80., 80.).color(WHITE).stroke_color(RED).stroke_weight(4.);
+//!     cell.polyline().weight(2.).points(points).color(BLUE);
+//! }
+//! ```
+//!
+//! Polygons, polylines, stroked paths, lines, ellipses and rects are recorded.
+//! The recording is cleared before each call to
+//! [`draw_at_time`](crate::Artwork::draw_at_time), and anything drawn to
+//! `base.draw` directly, like textures, text, meshes and filled paths, is left
+//! out of the SVG.
+use nannou::{
+    color::{IntoLinSrgba, LinSrgba},
+    prelude::*,
+};
+use std::{cell::RefCell, fmt::Write, rc::Rc};
+
+/// A linear RGBA color.
+type Rgba = [f32; 4];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Geometry {
+    Polygon(Vec<Vec2>),
+    Polyline(Vec<Vec2>),
+    Line(Vec2, Vec2),
+    Ellipse { center: Vec2, radii: Vec2 },
+    Rect { center: Vec2, size: Vec2 },
+}
+
+/// A recorded primitive.
+#[derive(Clone, Debug, PartialEq)]
+struct Shape {
+    geometry: Geometry,
+    /// From the coordinates of the primitive to the canvas, with y up.
+    transform: Mat3,
+    fill: Option<Rgba>,
+    /// The color and weight of the outline.
+    stroke: Option<(Rgba, f32)>,
+    round_join: bool,
+    round_caps: bool,
+}
+
+#[derive(Default)]
+struct Recording {
+    background: Option<Rgba>,
+    shapes: Vec<Shape>,
+}
+
+/// Draws to a nannou `Draw` and records the primitives for the SVG export.
+/// Its transforms return a new `VectorDraw` recording to the same document.
+#[derive(Clone)]
+pub struct VectorDraw {
+    draw: Draw,
+    transform: Mat3,
+    recording: Rc<RefCell<Recording>>,
+}
+
+impl VectorDraw {
+    pub(crate) fn new(draw: Draw) -> VectorDraw {
+        VectorDraw {
+            draw,
+            transform: Mat3::IDENTITY,
+            recording: Rc::new(RefCell::new(Recording::default())),
+        }
+    }
+
+    fn with(&self, draw: Draw, transform: Mat3) -> VectorDraw {
+        VectorDraw {
+            draw,
+            transform: self.transform * transform,
+            recording: self.recording.clone(),
+        }
+    }
+
+    /// The `Draw` the primitives are drawn to, with the same transform.
+    pub fn draw(&self) -> &Draw {
+        &self.draw
+    }
+
+    /// Translate the following primitives. Only `x` and `y` are exported.
+    pub fn translate(&self, v: Vec3) -> VectorDraw {
+        self.with(self.draw.translate(v), Mat3::from_translation(v.truncate()))
+    }
+
+    /// Rotate the following primitives around the z axis, in radians.
+    pub fn rotate(&self, radians: f32) -> VectorDraw {
+        self.with(self.draw.rotate(radians), Mat3::from_angle(radians))
+    }
+
+    pub fn scale(&self, scale: f32) -> VectorDraw {
+        self.with(self.draw.scale(scale), Mat3::from_scale(Vec2::splat(scale)))
+    }
+
+    pub fn background<C: IntoLinSrgba<f32>>(&self, color: C) {
+        let color = rgba(color);
+        self.draw.background().color(lin(color));
+        self.recording.borrow_mut().background = Some(color);
+    }
+
+    fn drawing(&self, geometry: Geometry) -> Drawing {
+        Drawing {
+            vector: self,
+            geometry: Some(geometry),
+            color: [1., 1., 1., 1.],
+            no_fill: false,
+            stroke: None,
+            weight: 1.,
+            round_join: false,
+            round_caps: false,
+        }
+    }
+
+    pub fn polygon(&self) -> Drawing {
+        self.drawing(Geometry::Polygon(vec![]))
+    }
+
+    pub fn polyline(&self) -> Drawing {
+        self.drawing(Geometry::Polyline(vec![]))
+    }
+
+    /// A stroked path through points, like nannou's `path().stroke()`. It is
+    /// exported as a polyline.
+    pub fn path(&self) -> Drawing {
+        self.polyline()
+    }
+
+    pub fn line(&self) -> Drawing {
+        self.drawing(Geometry::Line(Vec2::ZERO, Vec2::ZERO))
+    }
+
+    pub fn ellipse(&self) -> Drawing {
+        self.drawing(Geometry::Ellipse {
+            center: Vec2::ZERO,
+            radii: Vec2::splat(50.),
+        })
+    }
+
+    pub fn rect(&self) -> Drawing {
+        self.drawing(Geometry::Rect {
+            center: Vec2::ZERO,
+            size: Vec2::splat(100.),
+        })
+    }
+
+    /// Forget the primitives recorded so far.
+    pub(crate) fn clear(&self) {
+        let mut recording = self.recording.borrow_mut();
+        recording.background = None;
+        recording.shapes.clear();
+    }
+
+    /// The SVG document of the primitives recorded, on a canvas of `size`
+    /// pixels.
+    pub(crate) fn to_svg(&self, size: [u32; 2]) -> String {
+        let recording = self.recording.borrow();
+        document(recording.background, &recording.shapes, size)
+    }
+}
+
+/// A primitive being drawn, drawn and recorded when dropped. Like nannou's
+/// drawings, the color of lines and polylines is the color of their stroke.
+pub struct Drawing<'a> {
+    vector: &'a VectorDraw,
+    geometry: Option<Geometry>,
+    color: Rgba,
+    no_fill: bool,
+    stroke: Option<Rgba>,
+    weight: f32,
+    round_join: bool,
+    round_caps: bool,
+}
+
+impl Drawing<'_> {
+    /// The center of an ellipse or a rect.
+    pub fn x_y(self, x: f32, y: f32) -> Self {
+        self.xy(vec2(x, y))
+    }
+
+    /// The center of an ellipse or a rect.
+    pub fn xy(mut self, xy: Vec2) -> Self {
+        if let Some(Geometry::Ellipse { center, .. } | Geometry::Rect { center, .. }) =
+            &mut self.geometry
+        {
+            *center = xy;
+        }
+        self
+    }
+
+    /// The size of an ellipse or a rect.
+    pub fn w_h(mut self, w: f32, h: f32) -> Self {
+        match &mut self.geometry {
+            Some(Geometry::Ellipse { radii, .. }) => *radii = vec2(w, h) / 2.,
+            Some(Geometry::Rect { size, .. }) => *size = vec2(w, h),
+            _ => (),
+        }
+        self
+    }
+
+    /// The radius of an ellipse.
+    pub fn radius(self, radius: f32) -> Self {
+        self.w_h(2. * radius, 2. * radius)
+    }
+
+    /// The points of a polygon or a polyline.
+    pub fn points<I: IntoIterator<Item = Vec2>>(mut self, points: I) -> Self {
+        if let Some(Geometry::Polygon(p) | Geometry::Polyline(p)) = &mut self.geometry {
+            *p = points.into_iter().collect();
+        }
+        self
+    }
+
+    /// The start of a line.
+    pub fn start(mut self, point: Vec2) -> Self {
+        if let Some(Geometry::Line(start, _)) = &mut self.geometry {
+            *start = point;
+        }
+        self
+    }
+
+    /// The end of a line.
+    pub fn end(mut self, point: Vec2) -> Self {
+        if let Some(Geometry::Line(_, end)) = &mut self.geometry {
+            *end = point;
+        }
+        self
+    }
+
+    pub fn color<C: IntoLinSrgba<f32>>(mut self, color: C) -> Self {
+        self.color = rgba(color);
+        self
+    }
+
+    pub fn no_fill(mut self) -> Self {
+        self.no_fill = true;
+        self
+    }
+
+    /// The color of the outline of a polygon, an ellipse or a rect.
+    pub fn stroke_color<C: IntoLinSrgba<f32>>(mut self, color: C) -> Self {
+        self.stroke = Some(rgba(color));
+        self
+    }
+
+    pub fn stroke_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// The weight of a line or a polyline.
+    pub fn weight(self, weight: f32) -> Self {
+        self.stroke_weight(weight)
+    }
+
+    /// Round the joins of the outline of a polygon or of a polyline.
+    pub fn join_round(mut self) -> Self {
+        self.round_join = true;
+        self
+    }
+
+    /// Round the ends of a line or of a polyline.
+    pub fn caps_round(mut self) -> Self {
+        self.round_caps = true;
+        self
+    }
+
+    fn shape(&self, geometry: Geometry) -> Shape {
+        let (fill, stroke) = match geometry {
+            Geometry::Line(..) | Geometry::Polyline(_) => (None, Some((self.color, self.weight))),
+            _ => (
+                Some(self.color).filter(|_| !self.no_fill),
+                self.stroke.map(|c| (c, self.weight)),
+            ),
+        };
+        Shape {
+            geometry,
+            transform: self.vector.transform,
+            fill,
+            stroke,
+            round_join: self.round_join,
+            round_caps: self.round_caps,
+        }
+    }
+}
+
+impl Drop for Drawing<'_> {
+    fn drop(&mut self) {
+        let geometry = match self.geometry.take() {
+            Some(geometry) => geometry,
+            None => return,
+        };
+        let shape = self.shape(geometry);
+        draw_shape(&self.vector.draw, &shape);
+        self.vector.recording.borrow_mut().shapes.push(shape);
+    }
+}
+
+fn rgba<C: IntoLinSrgba<f32>>(color: C) -> Rgba {
+    let color = color.into_lin_srgba();
+    [color.red, color.green, color.blue, color.alpha]
+}
+
+fn lin(color: Rgba) -> LinSrgba {
+    LinSrgba::new(color[0], color[1], color[2], color[3])
+}
+
+/// Draw the shape with nannou.
+fn draw_shape(draw: &Draw, shape: &Shape) {
+    let fill = shape.fill.map(lin);
+    let stroke = shape.stroke.map(|(c, w)| (lin(c), w));
+    match &shape.geometry {
+        Geometry::Polygon(points) => {
+            let drawing = draw.polygon();
+            let drawing = match fill {
+                Some(color) => drawing.color(color),
+                None => drawing.no_fill(),
+            };
+            let drawing = match stroke {
+                Some((color, weight)) => drawing.stroke_color(color).stroke_weight(weight),
+                None => drawing,
+            };
+            let drawing = if shape.round_join {
+                drawing.join_round()
+            } else {
+                drawing
+            };
+            drawing.points(points.iter().copied());
+        }
+        Geometry::Polyline(points) => {
+            if let Some((color, weight)) = stroke {
+                let drawing = draw.polyline().weight(weight).color(color);
+                let drawing = if shape.round_join {
+                    drawing.join_round()
+                } else {
+                    drawing
+                };
+                let drawing = if shape.round_caps {
+                    drawing.caps_round()
+                } else {
+                    drawing
+                };
+                drawing.points(points.iter().copied());
+            }
+        }
+        Geometry::Line(start, end) => {
+            if let Some((color, weight)) = stroke {
+                let drawing = draw
+                    .line()
+                    .start(*start)
+                    .end(*end)
+                    .weight(weight)
+                    .color(color);
+                if shape.round_caps {
+                    drawing.caps_round();
+                }
+            }
+        }
+        Geometry::Ellipse { center, radii } => {
+            let drawing = draw.ellipse().xy(*center).w_h(2. * radii.x, 2. * radii.y);
+            let drawing = match fill {
+                Some(color) => drawing.color(color),
+                None => drawing.no_fill(),
+            };
+            if let Some((color, weight)) = stroke {
+                drawing.stroke_color(color).stroke_weight(weight);
+            }
+        }
+        Geometry::Rect { center, size } => {
+            let drawing = draw.rect().xy(*center).w_h(size.x, size.y);
+            let drawing = match fill {
+                Some(color) => drawing.color(color),
+                None => drawing.no_fill(),
+            };
+            if let Some((color, weight)) = stroke {
+                drawing.stroke_color(color).stroke_weight(weight);
+            }
+        }
+    }
+}
+
+/// A number with at most 3 decimals, without trailing zeros.
+fn num(x: f32) -> String {
+    let s = format!("{:.3}", x);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    match s {
+        "-0" => "0".to_string(),
+        s => s.to_string(),
+    }
+}
+
+/// The sRGB hex code of a linear color.
+fn hex(color: Rgba) -> String {
+    let encode = |c: f32| {
+        let c = c.clamp(0., 1.);
+        let c = if c <= 0.0031308 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1. / 2.4) - 0.055
+        };
+        (c * 255.).round() as u8
+    };
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        encode(color[0]),
+        encode(color[1]),
+        encode(color[2])
+    )
+}
+
+fn fill(color: Option<Rgba>) -> String {
+    match color {
+        Some(color) if color[3] < 1. => {
+            format!(r#" fill="{}" fill-opacity="{}""#, hex(color), num(color[3]))
+        }
+        Some(color) => format!(r#" fill="{}""#, hex(color)),
+        None => r#" fill="none""#.to_string(),
+    }
+}
+
+/// The fill and stroke attributes of `shape`, with the stroke weight scaled by
+/// `scale`.
+fn paint(shape: &Shape, scale: f32) -> String {
+    let mut attributes = fill(shape.fill);
+    if let Some((color, weight)) = shape.stroke {
+        write!(
+            attributes,
+            r#" stroke="{}" stroke-width="{}""#,
+            hex(color),
+            num(weight * scale)
+        )
+        .unwrap();
+        if color[3] < 1. {
+            write!(attributes, r#" stroke-opacity="{}""#, num(color[3])).unwrap();
+        }
+        if shape.round_caps {
+            attributes.push_str(r#" stroke-linecap="round""#);
+        }
+        if shape.round_join {
+            attributes.push_str(r#" stroke-linejoin="round""#);
+        }
+    }
+    attributes
+}
+
+fn points(transform: Mat3, points: &[Vec2]) -> String {
+    points
+        .iter()
+        .map(|&p| {
+            let p = transform.transform_point2(p);
+            format!("{},{}", num(p.x), num(p.y))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The SVG element of a shape. `canvas` is the transform from the centered
+/// canvas of nannou, with y up, to the SVG coordinates.
+fn element(shape: &Shape, canvas: Mat3) -> String {
+    let transform = canvas * shape.transform;
+    // Geometry transformed to the canvas has its strokes scaled too.
+    let scale = transform.determinant().abs().sqrt();
+    // Ellipses and rects keep their transform unless it only moves and scales
+    // them along the axes.
+    let aligned = transform.x_axis.y == 0. && transform.y_axis.x == 0.;
+    let matrix = format!(
+        r#" transform="matrix({} {} {} {} {} {})""#,
+        num(transform.x_axis.x),
+        num(transform.x_axis.y),
+        num(transform.y_axis.x),
+        num(transform.y_axis.y),
+        num(transform.z_axis.x),
+        num(transform.z_axis.y)
+    );
+    match &shape.geometry {
+        Geometry::Polygon(p) => format!(
+            r#"<polygon points="{}"{}/>"#,
+            points(transform, p),
+            paint(shape, scale)
+        ),
+        Geometry::Polyline(p) => format!(
+            r#"<polyline points="{}"{}/>"#,
+            points(transform, p),
+            paint(shape, scale)
+        ),
+        Geometry::Line(start, end) => {
+            let (a, b) = (
+                transform.transform_point2(*start),
+                transform.transform_point2(*end),
+            );
+            format!(
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}"{}/>"#,
+                num(a.x),
+                num(a.y),
+                num(b.x),
+                num(b.y),
+                paint(shape, scale)
+            )
+        }
+        Geometry::Ellipse { center, radii } if aligned => {
+            let c = transform.transform_point2(*center);
+            let r = *radii * vec2(transform.x_axis.x, transform.y_axis.y).abs();
+            format!(
+                r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}"{}/>"#,
+                num(c.x),
+                num(c.y),
+                num(r.x),
+                num(r.y),
+                paint(shape, scale)
+            )
+        }
+        Geometry::Ellipse { center, radii } => format!(
+            r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}"{}{}/>"#,
+            num(center.x),
+            num(center.y),
+            num(radii.x),
+            num(radii.y),
+            matrix,
+            paint(shape, 1.)
+        ),
+        Geometry::Rect { center, size } if aligned => {
+            let c = transform.transform_point2(*center);
+            let s = *size * vec2(transform.x_axis.x, transform.y_axis.y).abs();
+            format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}"{}/>"#,
+                num(c.x - s.x / 2.),
+                num(c.y - s.y / 2.),
+                num(s.x),
+                num(s.y),
+                paint(shape, scale)
+            )
+        }
+        Geometry::Rect { center, size } => format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}"{}{}/>"#,
+            num(center.x - size.x / 2.),
+            num(center.y - size.y / 2.),
+            num(size.x),
+            num(size.y),
+            matrix,
+            paint(shape, 1.)
+        ),
+    }
+}
+
+/// The SVG document of `shapes` drawn over `background`, on a canvas of
+/// `size` pixels.
+fn document(background: Option<Rgba>, shapes: &[Shape], size: [u32; 2]) -> String {
+    let [w, h] = size;
+    let canvas = Mat3::from_cols(
+        vec3(1., 0., 0.),
+        vec3(0., -1., 0.),
+        vec3(w as f32 / 2., h as f32 / 2., 1.),
+    );
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = w,
+        h = h
+    );
+    svg.push('\n');
+    if let Some(color) = background {
+        writeln!(
+            svg,
+            r#"  <rect width="{}" height="{}"{}/>"#,
+            w,
+            h,
+            fill(Some(color))
+        )
+        .unwrap();
+    }
+    for shape in shapes {
+        writeln!(svg, "  {}", element(shape, canvas)).unwrap();
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{document, hex, num, Geometry, Shape, VectorDraw};
+    use nannou::prelude::*;
+
+    #[test]
+    fn numbers_and_colors() {
+        assert_eq!(num(1.5), "1.5");
+        assert_eq!(num(2.), "2");
+        assert_eq!(num(-0.0001), "0");
+        assert_eq!(num(1. / 3.), "0.333");
+        assert_eq!(hex([0., 1., 0.2158, 1.]), "#00ff80");
+    }
+
+    #[test]
+    fn shapes_at_canvas_coordinates() {
+        let shapes = [
+            Shape {
+                geometry: Geometry::Polygon(vec![vec2(0., 0.), vec2(100., 0.), vec2(0., 100.)]),
+                transform: Mat3::from_translation(vec2(10., 10.)),
+                fill: Some([1., 0., 0., 0.5]),
+                stroke: None,
+                round_join: false,
+                round_caps: false,
+            },
+            Shape {
+                geometry: Geometry::Line(vec2(-50., 0.), vec2(50., 0.)),
+                transform: Mat3::from_scale(vec2(2., 2.)),
+                fill: None,
+                stroke: Some(([0., 0., 0., 1.], 1.5)),
+                round_join: false,
+                round_caps: false,
+            },
+            Shape {
+                geometry: Geometry::Ellipse {
+                    center: vec2(0., 0.),
+                    radii: vec2(20., 10.),
+                },
+                transform: Mat3::IDENTITY,
+                fill: Some([1., 1., 1., 1.]),
+                stroke: None,
+                round_join: false,
+                round_caps: false,
+            },
+            Shape {
+                geometry: Geometry::Rect {
+                    center: vec2(0., 0.),
+                    size: vec2(10., 10.),
+                },
+                transform: Mat3::from_angle(PI / 2.),
+                fill: None,
+                stroke: Some(([0., 0., 1., 1.], 2.)),
+                round_join: true,
+                round_caps: false,
+            },
+        ];
+        let svg = document(Some([0., 0., 0., 1.]), &shapes, [200, 100]);
+        let lines: Vec<&str> = svg.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100" viewBox="0 0 200 100">"#,
+                r##"  <rect width="200" height="100" fill="#000000"/>"##,
+                r##"  <polygon points="110,40 210,40 110,-60" fill="#ff0000" fill-opacity="0.5"/>"##,
+                r##"  <line x1="0" y1="50" x2="200" y2="50" fill="none" stroke="#000000" stroke-width="3"/>"##,
+                r##"  <ellipse cx="100" cy="50" rx="20" ry="10" fill="#ffffff"/>"##,
+                r##"  <rect x="-5" y="-5" width="10" height="10" transform="matrix(0 -1 -1 0 100 50)" fill="none" stroke="#0000ff" stroke-width="2" stroke-linejoin="round"/>"##,
+                "</svg>",
+            ]
+        );
+    }
+
+    #[test]
+    fn record_through_transforms() {
+        let vector = VectorDraw::new(Draw::new());
+        vector.background(BLACK);
+        vector
+            .path()
+            .weight(1.)
+            .points([vec2(0., 0.), vec2(10., 10.)])
+            .color(BLUE);
+        let cell = vector.translate(vec3(50., 0., 0.)).rotate(PI / 2.);
+        cell.line()
+            .start(vec2(0., 0.))
+            .end(vec2(10., 0.))
+            .weight(2.)
+            .caps_round()
+            .color(WHITE);
+        cell.scale(2.).rect().w_h(10., 10.).color(RED);
+        let svg = vector.to_svg([200, 100]);
+        let lines: Vec<&str> = svg.lines().skip(1).collect();
+        assert_eq!(
+            lines,
+            [
+                r##"  <rect width="200" height="100" fill="#000000"/>"##,
+                r##"  <polyline points="100,50 110,40" fill="none" stroke="#0000ff" stroke-width="1"/>"##,
+                r##"  <line x1="150" y1="50" x2="150" y2="40" fill="none" stroke="#ffffff" stroke-width="2" stroke-linecap="round"/>"##,
+                r##"  <rect x="-5" y="-5" width="10" height="10" transform="matrix(0 -2 -2 0 150 50)" fill="#ff0000"/>"##,
+                "</svg>",
+            ]
+        );
+
+        vector.clear();
+        assert_eq!(vector.to_svg([200, 100]).lines().count(), 2);
+    }
+}
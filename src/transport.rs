@@ -0,0 +1,186 @@
+//! Playback of the animation in the preview window.
+//!
+//! The animation plays in real time by default, and can be paused, stepped
+//! frame by frame, sped up or slowed down. In mouse-scrub mode, moving the
+//! mouse horizontally across the window moves through the animation instead.
+//! Recording ignores the transport and always renders every frame in order.
+
+/// The slowest and fastest playback speeds.
+const MIN_SPEED: f64 = 0.125;
+const MAX_SPEED: f64 = 8.;
+
+pub(crate) struct Transport {
+    pub playing: bool,
+    /// Playback speed, 1 being real time.
+    pub speed: f64,
+    /// Whether playback wraps around at the end, or stops on the last frame.
+    pub looping: bool,
+    /// Whether the mouse position controls the displayed frame.
+    pub scrubbing: bool,
+    /// The playhead, in frames. It is fractional so that slow speeds still
+    /// advance.
+    position: f64,
+    /// The horizontal position of the mouse at the previous update. The mouse
+    /// only scrubs through the animation when it moves, so that the frame can
+    /// also be set remotely.
+    last_mouse_x: f32,
+}
+
+impl Transport {
+    pub fn new() -> Transport {
+        Transport {
+            playing: true,
+            speed: 1.,
+            looping: true,
+            scrubbing: false,
+            position: 0.,
+            last_mouse_x: f32::NAN,
+        }
+    }
+
+    /// The frame to display, in an animation of `n_frames` frames.
+    pub fn frame(&self, n_frames: u32) -> u32 {
+        (self.position as u32).min(n_frames.saturating_sub(1))
+    }
+
+    /// Move the playhead by `dt` seconds of playback at `fps` frames per
+    /// second.
+    pub fn advance(&mut self, dt: f64, fps: u32, n_frames: u32) {
+        if !self.playing || n_frames == 0 {
+            return;
+        }
+        self.position += dt * fps as f64 * self.speed;
+        if self.position >= n_frames as f64 {
+            if self.looping {
+                self.position %= n_frames as f64;
+            } else {
+                self.position = (n_frames - 1) as f64;
+                self.playing = false;
+            }
+        }
+    }
+
+    pub fn toggle_playing(&mut self, n_frames: u32) {
+        // Playing from the last frame without looping restarts the animation.
+        if !self.playing && !self.looping && self.frame(n_frames) + 1 >= n_frames {
+            self.position = 0.;
+        }
+        self.playing = !self.playing;
+    }
+
+    /// Pause and move by `frames` frames, wrapping around the ends if looping.
+    pub fn step(&mut self, frames: i64, n_frames: u32) {
+        self.playing = false;
+        let frame = self.frame(n_frames) as i64 + frames;
+        let frame = if self.looping {
+            frame.rem_euclid(n_frames as i64)
+        } else {
+            frame.clamp(0, n_frames as i64 - 1)
+        };
+        self.position = frame as f64;
+    }
+
+    /// Move to `frame`, wrapping it around the animation.
+    pub fn jump(&mut self, frame: u32, n_frames: u32) {
+        self.position = (frame % n_frames) as f64;
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2.).min(MAX_SPEED);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2.).max(MIN_SPEED);
+    }
+
+    /// Toggle mouse-scrub mode. Playback pauses while scrubbing.
+    pub fn toggle_scrubbing(&mut self) {
+        self.scrubbing = !self.scrubbing;
+        if self.scrubbing {
+            self.playing = false;
+        }
+        self.last_mouse_x = f32::NAN;
+    }
+
+    /// Follow the mouse at `mouse_x` in a window of width `w`, if it moved
+    /// since the last call.
+    pub fn scrub(&mut self, mouse_x: f32, w: f32, n_frames: u32) {
+        if mouse_x == self.last_mouse_x {
+            return;
+        }
+        self.last_mouse_x = mouse_x;
+        let pos = 2. * (4. * mouse_x + w) / w;
+        self.position = ((pos * n_frames as f32) as u32 % n_frames) as f64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transport;
+
+    #[test]
+    fn play_and_loop() {
+        let mut transport = Transport::new();
+        transport.advance(0.5, 60, 120);
+        assert_eq!(transport.frame(120), 30);
+        transport.speed = 0.25;
+        transport.advance(0.1, 60, 120);
+        assert_eq!(transport.frame(120), 31);
+        transport.speed = 2.;
+        transport.advance(1., 60, 120);
+        assert_eq!(transport.frame(120), 31);
+
+        transport.looping = false;
+        transport.advance(10., 60, 120);
+        assert_eq!(transport.frame(120), 119);
+        assert!(!transport.playing);
+        transport.toggle_playing(120);
+        assert_eq!(transport.frame(120), 0);
+        assert!(transport.playing);
+    }
+
+    #[test]
+    fn step_and_jump() {
+        let mut transport = Transport::new();
+        transport.step(-1, 120);
+        assert!(!transport.playing);
+        assert_eq!(transport.frame(120), 119);
+        transport.step(3, 120);
+        assert_eq!(transport.frame(120), 2);
+        transport.looping = false;
+        transport.step(-5, 120);
+        assert_eq!(transport.frame(120), 0);
+        transport.jump(250, 120);
+        assert_eq!(transport.frame(120), 10);
+        // Paused playback does not move.
+        transport.advance(1., 60, 120);
+        assert_eq!(transport.frame(120), 10);
+    }
+
+    #[test]
+    fn speed_limits() {
+        let mut transport = Transport::new();
+        for _ in 0..10 {
+            transport.faster();
+        }
+        assert_eq!(transport.speed, 8.);
+        for _ in 0..10 {
+            transport.slower();
+        }
+        assert_eq!(transport.speed, 0.125);
+    }
+
+    #[test]
+    fn scrub_only_when_the_mouse_moves() {
+        let mut transport = Transport::new();
+        transport.toggle_scrubbing();
+        assert!(!transport.playing);
+        transport.scrub(-100., 800., 120);
+        assert_eq!(transport.frame(120), 0);
+        transport.jump(40, 120);
+        transport.scrub(-100., 800., 120);
+        assert_eq!(transport.frame(120), 40);
+        transport.scrub(-75., 800., 120);
+        assert_eq!(transport.frame(120), 30);
+    }
+}
@@ -1,7 +1,7 @@
 use nannou::{
     color::{hsla, Hsla},
     prelude::{Vec3, PI},
-    rand::random_f32,
+    rand::Rng,
 };
 
 fn point_to_hsl(point: Vec3) -> Vec3 {
@@ -48,11 +48,12 @@ fn random_hsl_pair(
     start_hue: f32,
     saturations: (f32, f32),
     lightnesses: (f32, f32),
+    rng: &mut impl Rng,
 ) -> (Vec3, Vec3) {
     (
         Vec3::new(start_hue, saturations.0, lightnesses.0),
         Vec3::new(
-            (start_hue + 60. + random_f32() * 180.) % 360.,
+            (start_hue + 60. + rng.gen::<f32>() * 180.) % 360.,
             saturations.1,
             lightnesses.1,
         ),
@@ -63,16 +64,17 @@ fn random_hsl_triple(
     start_hue: f32,
     saturations: (f32, f32, f32),
     lightnesses: (f32, f32, f32),
+    rng: &mut impl Rng,
 ) -> (Vec3, Vec3, Vec3) {
     (
         Vec3::new(start_hue, saturations.0, lightnesses.0),
         Vec3::new(
-            (start_hue + 60. + random_f32() * 180.) % 360.,
+            (start_hue + 60. + rng.gen::<f32>() * 180.) % 360.,
             saturations.1,
             lightnesses.1,
         ),
         Vec3::new(
-            (start_hue + 60. + random_f32() * 180.) % 360.,
+            (start_hue + 60. + rng.gen::<f32>() * 180.) % 360.,
             saturations.2,
             lightnesses.2,
         ),
@@ -225,12 +227,15 @@ fn pos_function_to_fxfyfz(
     }
 }
 
-pub fn get_random_color_palette(length: usize, pos_functions: PosFunctions) -> Vec<Hsla> {
-    let (c1, c2) = random_hsl_pair(
-        360. * random_f32(),
-        (random_f32(), random_f32()),
-        (0.75 + random_f32() * 0.2, 0.3 + random_f32() * 0.2),
-    );
+pub fn get_random_color_palette(
+    length: usize,
+    pos_functions: PosFunctions,
+    rng: &mut impl Rng,
+) -> Vec<Hsla> {
+    let start_hue = 360. * rng.gen::<f32>();
+    let saturations = (rng.gen(), rng.gen());
+    let lightnesses = (0.75 + rng.gen::<f32>() * 0.2, 0.3 + rng.gen::<f32>() * 0.2);
+    let (c1, c2) = random_hsl_pair(start_hue, saturations, lightnesses, rng);
     let (fx, fy, fz) = pos_function_to_fxfyfz(pos_functions);
 
     vectors_on_line(
@@ -248,16 +253,19 @@ pub fn get_random_color_palette(length: usize, pos_functions: PosFunctions) -> V
     .collect()
 }
 
-pub fn get_random_color_palette3(length: usize, pos_functions: PosFunctions) -> Vec<Hsla> {
-    let (c1, c2, c3) = random_hsl_triple(
-        360. * random_f32(),
-        (random_f32(), random_f32(), random_f32()),
-        (
-            0.75 + random_f32() * 0.2,
-            0.3 + random_f32() * 0.2,
-            0.75 + random_f32() * 0.2,
-        ),
+pub fn get_random_color_palette3(
+    length: usize,
+    pos_functions: PosFunctions,
+    rng: &mut impl Rng,
+) -> Vec<Hsla> {
+    let start_hue = 360. * rng.gen::<f32>();
+    let saturations = (rng.gen(), rng.gen(), rng.gen());
+    let lightnesses = (
+        0.75 + rng.gen::<f32>() * 0.2,
+        0.3 + rng.gen::<f32>() * 0.2,
+        0.75 + rng.gen::<f32>() * 0.2,
     );
+    let (c1, c2, c3) = random_hsl_triple(start_hue, saturations, lightnesses, rng);
     let (fx, fy, fz) = pos_function_to_fxfyfz(pos_functions);
 
     vectors_on_line(
@@ -286,13 +294,14 @@ pub fn get_random_color_palette3(length: usize, pos_functions: PosFunctions) ->
 
 mod tests {
     use super::*;
+    use nannou::rand::{rngs::StdRng, SeedableRng};
 
     #[test]
     fn generate_pair_of_hsl() {
         let base_hue = 128.;
         let sat = (0.5, 0.43);
         let brightness = (0.75, 0.32);
-        let (c1, c2) = random_hsl_pair(128., sat, brightness);
+        let (c1, c2) = random_hsl_pair(128., sat, brightness, &mut StdRng::seed_from_u64(0));
         assert_eq!(c1.x, base_hue);
         assert_ne!(c2.x, c1.x);
         assert_eq!(c1.y, sat.0);
@@ -305,7 +314,7 @@ mod tests {
         let base_hue = 128.;
         let sat = (0.5, 0.43, 0.8);
         let brightness = (0.75, 0.32, 0.4);
-        let (c1, c2, c3) = random_hsl_triple(128., sat, brightness);
+        let (c1, c2, c3) = random_hsl_triple(128., sat, brightness, &mut StdRng::seed_from_u64(0));
         assert_eq!(c1.x, base_hue);
         assert_ne!(c2.x, c1.x);
         assert_ne!(c3.x, c1.x);
@@ -0,0 +1,82 @@
+//! Easing curves that can be chosen by name, for instance in a file.
+use nannou::ease;
+use serde::{Deserialize, Serialize};
+
+/// One of the easing functions of [`nannou::ease`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    QuartIn,
+    QuartOut,
+    QuartInOut,
+    QuintIn,
+    QuintOut,
+    QuintInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    CircIn,
+    CircOut,
+    CircInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+}
+
+impl Easing {
+    /// The eased value of `t`, between 0 and 1. Some curves, like `Back` and
+    /// `Elastic`, overshoot this range.
+    pub fn apply(self, t: f32) -> f32 {
+        let f: fn(f32, f32, f32, f32) -> f32 = match self {
+            Easing::Linear => return t,
+            Easing::QuadIn => ease::quad::ease_in,
+            Easing::QuadOut => ease::quad::ease_out,
+            Easing::QuadInOut => ease::quad::ease_in_out,
+            Easing::CubicIn => ease::cubic::ease_in,
+            Easing::CubicOut => ease::cubic::ease_out,
+            Easing::CubicInOut => ease::cubic::ease_in_out,
+            Easing::QuartIn => ease::quart::ease_in,
+            Easing::QuartOut => ease::quart::ease_out,
+            Easing::QuartInOut => ease::quart::ease_in_out,
+            Easing::QuintIn => ease::quint::ease_in,
+            Easing::QuintOut => ease::quint::ease_out,
+            Easing::QuintInOut => ease::quint::ease_in_out,
+            Easing::SineIn => ease::sine::ease_in,
+            Easing::SineOut => ease::sine::ease_out,
+            Easing::SineInOut => ease::sine::ease_in_out,
+            Easing::ExpoIn => ease::expo::ease_in,
+            Easing::ExpoOut => ease::expo::ease_out,
+            Easing::ExpoInOut => ease::expo::ease_in_out,
+            Easing::CircIn => ease::circ::ease_in,
+            Easing::CircOut => ease::circ::ease_out,
+            Easing::CircInOut => ease::circ::ease_in_out,
+            Easing::BackIn => ease::back::ease_in,
+            Easing::BackOut => ease::back::ease_out,
+            Easing::BackInOut => ease::back::ease_in_out,
+            Easing::ElasticIn => ease::elastic::ease_in,
+            Easing::ElasticOut => ease::elastic::ease_out,
+            Easing::ElasticInOut => ease::elastic::ease_in_out,
+            Easing::BounceIn => ease::bounce::ease_in,
+            Easing::BounceOut => ease::bounce::ease_out,
+            Easing::BounceInOut => ease::bounce::ease_in_out,
+        };
+        f(t, 0., 1., 1.)
+    }
+}
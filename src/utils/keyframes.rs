@@ -0,0 +1,453 @@
+//! Animated values defined by keyframes.
+//!
+//! A [`Track`] interpolates between values set at given times, with an easing
+//! curve between each pair of keyframes. Tracks can be built in code, or loaded
+//! from a JSON or TOML file so that the timing of an artwork can be adjusted
+//! without touching its drawing code:
+//!
+//! ``` json
+//! {
+//!     "radius": [
+//!         { "time": 0.0, "value": 10, "easing": "cubic_out" },
+//!         { "time": 0.5, "value": 200, "bezier": [0.2, 0.0, 0.1, 1.0] },
+//!         { "time": 1.0, "value": 10 }
+//!     ],
+//!     "center": [
+//!         { "time": 0.0, "value": [-300, 0] },
+//!         { "time": 1.0, "value": [300, 0] }
+//!     ],
+//!     "fill": [
+//!         { "time": 0.0, "value": [1.0, 0.2, 0.1] },
+//!         { "time": 1.0, "value": [0.1, 0.2, 1.0, 0.5] }
+//!     ]
+//! }
+//! ```
+//!
+//! ```ignore
+//! let tracks = Tracks::load(Path::new("assets/tracks.json")).unwrap();
+//! let radius = tracks.scalar("radius");
+//! // In `draw_at_time`:
+//! draw.ellipse().radius(radius.sample(time));
+//! ```
+//!
+//! Times are those of the artwork, between 0 and 1, and tracks can be sampled
+//! at any fractional time, such as the sub-frames of the motion blur.
+use super::easing::Easing;
+use nannou::{
+    color::Srgba,
+    geom::{Vec2, Vec3},
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+/// A value that can be interpolated between two keyframes.
+pub trait Animatable: Clone {
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Animatable for f32 {
+    fn lerp(&self, other: &f32, t: f32) -> f32 {
+        self + (other - self) * t
+    }
+}
+
+impl Animatable for Vec2 {
+    fn lerp(&self, other: &Vec2, t: f32) -> Vec2 {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Animatable for Vec3 {
+    fn lerp(&self, other: &Vec3, t: f32) -> Vec3 {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Animatable for Srgba {
+    fn lerp(&self, other: &Srgba, t: f32) -> Srgba {
+        Srgba::new(
+            self.red.lerp(&other.red, t),
+            self.green.lerp(&other.green, t),
+            self.blue.lerp(&other.blue, t),
+            self.alpha.lerp(&other.alpha, t),
+        )
+    }
+}
+
+impl Animatable for Vec<f32> {
+    fn lerp(&self, other: &Vec<f32>, t: f32) -> Vec<f32> {
+        self.iter().zip(other).map(|(a, b)| a.lerp(b, t)).collect()
+    }
+}
+
+/// A value at a given time. The easing and bezier handles shape the
+/// interpolation towards the next keyframe.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe<T> {
+    pub time: f64,
+    pub value: T,
+    #[serde(default)]
+    pub easing: Easing,
+    /// The handles `[x1, y1, x2, y2]` of a cubic bezier timing curve from
+    /// `(0, 0)` to `(1, 1)`, as in CSS. They replace the easing when given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bezier: Option<[f32; 4]>,
+}
+
+impl<T> Keyframe<T> {
+    /// The eased progress between this keyframe and the next one, for `t`
+    /// between 0 and 1.
+    fn ease(&self, t: f32) -> f32 {
+        match self.bezier {
+            Some(handles) => cubic_bezier(handles, t),
+            None => self.easing.apply(t),
+        }
+    }
+}
+
+/// The y coordinate of the timing curve with the given handles where its x
+/// coordinate is `x`.
+fn cubic_bezier([x1, y1, x2, y2]: [f32; 4], x: f32) -> f32 {
+    let bezier = |p1: f32, p2: f32, s: f32| {
+        3. * (1. - s) * (1. - s) * s * p1 + 3. * (1. - s) * s * s * p2 + s * s * s
+    };
+    let slope = |p1: f32, p2: f32, s: f32| {
+        3. * (1. - s) * (1. - s) * p1 + 6. * (1. - s) * s * (p2 - p1) + 3. * s * s * (1. - p2)
+    };
+    // Newton's method converges quickly for most curves, and bisection takes
+    // over when the slope is too flat.
+    let mut s = x;
+    for _ in 0..8 {
+        let error = bezier(x1, x2, s) - x;
+        let derivative = slope(x1, x2, s);
+        if error.abs() < 1e-6 {
+            return bezier(y1, y2, s);
+        }
+        if derivative.abs() < 1e-6 {
+            break;
+        }
+        s = (s - error / derivative).clamp(0., 1.);
+    }
+    let (mut low, mut high) = (0., 1.);
+    s = x;
+    for _ in 0..32 {
+        if bezier(x1, x2, s) < x {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) / 2.;
+    }
+    bezier(y1, y2, s)
+}
+
+/// Keyframes of a value, sorted by time.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+    /// The index of the keyframe added last, which `eased` and `bezier`
+    /// change.
+    #[serde(skip)]
+    added: Option<usize>,
+}
+
+impl<T> Default for Track<T> {
+    fn default() -> Track<T> {
+        Track {
+            keyframes: vec![],
+            added: None,
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for Track<T> {
+    fn eq(&self, other: &Track<T>) -> bool {
+        self.keyframes == other.keyframes
+    }
+}
+
+impl<T> Track<T> {
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    /// Convert the values of the keyframes, keeping their timing.
+    pub fn map<U, F: Fn(&T) -> U>(&self, f: F) -> Track<U> {
+        Track {
+            keyframes: self
+                .keyframes
+                .iter()
+                .map(|k| Keyframe {
+                    time: k.time,
+                    value: f(&k.value),
+                    easing: k.easing,
+                    bezier: k.bezier,
+                })
+                .collect(),
+            added: self.added,
+        }
+    }
+}
+
+impl<T: Animatable> Track<T> {
+    pub fn new() -> Track<T> {
+        Track::default()
+    }
+
+    /// Add a keyframe, interpolated linearly towards the next one. Keyframes
+    /// can be added in any order.
+    pub fn key(mut self, time: f64, value: T) -> Track<T> {
+        let index = self.keyframes.partition_point(|k| k.time <= time);
+        self.added = Some(index);
+        self.keyframes.insert(
+            index,
+            Keyframe {
+                time,
+                value,
+                easing: Easing::Linear,
+                bezier: None,
+            },
+        );
+        self
+    }
+
+    /// Set the easing from the keyframe just added to the next one in time.
+    pub fn eased(mut self, easing: Easing) -> Track<T> {
+        if let Some(added) = self.added {
+            self.keyframes[added].easing = easing;
+        }
+        self
+    }
+
+    /// Set the bezier handles from the keyframe just added to the next one in
+    /// time.
+    pub fn bezier(mut self, x1: f32, y1: f32, x2: f32, y2: f32) -> Track<T> {
+        if let Some(added) = self.added {
+            self.keyframes[added].bezier = Some([x1, y1, x2, y2]);
+        }
+        self
+    }
+
+    /// The value at `time`. It holds the first and last keyframes before and
+    /// after them.
+    ///
+    /// Panics if the track is empty.
+    pub fn sample(&self, time: f64) -> T {
+        let index = self.keyframes.partition_point(|k| k.time <= time);
+        if index == 0 {
+            return self.keyframes[0].value.clone();
+        }
+        let previous = &self.keyframes[index - 1];
+        let next = match self.keyframes.get(index) {
+            Some(next) => next,
+            None => return previous.value.clone(),
+        };
+        let t = ((time - previous.time) / (next.time - previous.time)) as f32;
+        previous.value.lerp(&next.value, previous.ease(t))
+    }
+}
+
+/// A value of a keyframe in a file: a number, or an array of components.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Components {
+    Scalar(f32),
+    Vector(Vec<f32>),
+}
+
+impl Components {
+    fn as_slice(&self) -> &[f32] {
+        match self {
+            Components::Scalar(value) => std::slice::from_ref(value),
+            Components::Vector(values) => values,
+        }
+    }
+}
+
+/// Named tracks loaded from a file.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Tracks {
+    tracks: BTreeMap<String, Track<Components>>,
+}
+
+impl Tracks {
+    /// Load tracks from a JSON file, or from a TOML file if the extension of
+    /// the path is `toml`.
+    pub fn load(path: &Path) -> io::Result<Tracks> {
+        let content = fs::read_to_string(path)?;
+        let mut tracks: Tracks = if path.extension().map_or(false, |e| e == "toml") {
+            toml::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+        } else {
+            serde_json::from_str(&content)?
+        };
+        for (name, track) in tracks.tracks.iter_mut() {
+            if track.keyframes.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("track {} has no keyframes", name),
+                ));
+            }
+            track.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        }
+        Ok(tracks)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.tracks.keys().map(|name| name.as_str())
+    }
+
+    /// The track called `name`, whose values must have `n` components.
+    ///
+    /// Panics otherwise, as the file does not match the artwork.
+    fn components(&self, name: &str, n: usize) -> Track<Vec<f32>> {
+        let track = self
+            .tracks
+            .get(name)
+            .unwrap_or_else(|| panic!("Unknown track {}", name));
+        track.map(|value| {
+            let components = value.as_slice();
+            if components.len() != n {
+                panic!(
+                    "Track {} has values with {} components instead of {}",
+                    name,
+                    components.len(),
+                    n
+                );
+            }
+            components.to_vec()
+        })
+    }
+
+    pub fn scalar(&self, name: &str) -> Track<f32> {
+        self.components(name, 1).map(|v| v[0])
+    }
+
+    pub fn vec2(&self, name: &str) -> Track<Vec2> {
+        self.components(name, 2).map(|v| Vec2::new(v[0], v[1]))
+    }
+
+    pub fn vec3(&self, name: &str) -> Track<Vec3> {
+        self.components(name, 3)
+            .map(|v| Vec3::new(v[0], v[1], v[2]))
+    }
+
+    /// A color track, whose values are `[red, green, blue]` or
+    /// `[red, green, blue, alpha]` between 0 and 1. Keyframes without alpha
+    /// are opaque.
+    pub fn color(&self, name: &str) -> Track<Srgba> {
+        let track = self
+            .tracks
+            .get(name)
+            .unwrap_or_else(|| panic!("Unknown track {}", name));
+        track.map(|value| match *value.as_slice() {
+            [red, green, blue] => Srgba::new(red, green, blue, 1.),
+            [red, green, blue, alpha] => Srgba::new(red, green, blue, alpha),
+            ref components => panic!(
+                "Track {} has values with {} components instead of 3 or 4",
+                name,
+                components.len()
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cubic_bezier, Track, Tracks};
+    use crate::utils::easing::Easing;
+    use nannou::color::Srgba;
+
+    #[test]
+    fn sample_between_keyframes() {
+        let track = Track::new()
+            .key(0., 0.)
+            .key(0.5, 10.)
+            .eased(Easing::CubicIn)
+            .key(1., 20.);
+        assert_eq!(track.sample(-1.), 0.);
+        assert_eq!(track.sample(0.25), 5.);
+        assert_eq!(track.sample(0.5), 10.);
+        assert_eq!(track.sample(0.75), 11.25);
+        assert_eq!(track.sample(2.), 20.);
+        // Sub-frames are interpolated too.
+        assert!(track.sample(0.1001) > track.sample(0.1));
+    }
+
+    #[test]
+    fn bezier_handles() {
+        // These handles give a straight line.
+        assert!((cubic_bezier([1. / 3., 1. / 3., 2. / 3., 2. / 3.], 0.3) - 0.3).abs() < 1e-4);
+        // CSS `ease-in-out` is symmetric.
+        let ease_in_out = [0.42, 0., 0.58, 1.];
+        assert!((cubic_bezier(ease_in_out, 0.5) - 0.5).abs() < 1e-4);
+        assert!(
+            (cubic_bezier(ease_in_out, 0.2) + cubic_bezier(ease_in_out, 0.8) - 1.).abs() < 1e-4
+        );
+        assert!(cubic_bezier(ease_in_out, 0.2) < 0.2);
+    }
+
+    #[test]
+    fn load_from_json() {
+        let path = std::env::temp_dir().join("artworks-keyframes-test.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "radius": [
+                    { "time": 1.0, "value": 20 },
+                    { "time": 0.0, "value": 10, "easing": "cubic_in" }
+                ],
+                "fill": [
+                    { "time": 0.0, "value": [1.0, 0.0, 0.0] },
+                    { "time": 1.0, "value": [0.0, 0.0, 1.0] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let tracks = Tracks::load(&path).unwrap();
+        assert_eq!(tracks.names().collect::<Vec<_>>(), vec!["fill", "radius"]);
+        assert_eq!(tracks.scalar("radius").sample(0.5), 11.25);
+        let fill = tracks.color("fill").sample(0.5);
+        assert_eq!((fill.red, fill.blue, fill.alpha), (0.5, 0.5, 1.));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn easing_of_the_keyframe_just_added() {
+        let track = Track::new()
+            .key(1., 20.)
+            .key(0., 0.)
+            .eased(Easing::CubicIn)
+            .key(0.5, 10.);
+        assert_eq!(track.keyframes()[0].easing, Easing::CubicIn);
+        assert_eq!(track.keyframes()[2].easing, Easing::Linear);
+        assert_eq!(track.sample(0.25), 1.25);
+    }
+
+    #[test]
+    fn colors_with_and_without_alpha() {
+        // The track of the example of the module documentation.
+        let tracks: Tracks = serde_json::from_str(
+            r#"{
+                "fill": [
+                    { "time": 0.0, "value": [1.0, 0.2, 0.1] },
+                    { "time": 1.0, "value": [0.1, 0.2, 1.0, 0.5] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let fill = tracks.color("fill");
+        assert_eq!(fill.sample(0.).alpha, 1.);
+        assert_eq!(fill.sample(0.5).alpha, 0.75);
+        assert_eq!(fill.sample(1.), Srgba::new(0.1, 0.2, 1.0, 0.5));
+    }
+
+    #[test]
+    #[should_panic(expected = "Track radius has values with 1 components instead of 2")]
+    fn wrong_dimension() {
+        let tracks: Tracks =
+            serde_json::from_str(r#"{ "radius": [{ "time": 0.0, "value": 1 }] }"#).unwrap();
+        tracks.vec2("radius");
+    }
+}
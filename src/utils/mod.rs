@@ -1 +1,5 @@
 pub mod colors;
+pub mod easing;
+pub mod keyframes;
+pub mod noise;
+pub mod timeline;
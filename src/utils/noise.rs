@@ -0,0 +1,237 @@
+//! Noise that loops over the time of an animation.
+//!
+//! The time of the artwork is mapped to a circle in the last two dimensions of
+//! a 4D OpenSimplex noise, so every function of [`LoopNoise`] returns exactly
+//! the same value at time 0 and 1.
+//!
+//! ```ignore
+//! fn new(base: BaseModel) -> Model {
+//!     let noise = LoopNoise::new(base.seed).octaves(4);
+//!     Model { base, noise }
+//! }
+//!
+//! fn draw_at_time(&mut self, time: f64) {
+//!     // Follow the seed picked with S.
+//!     self.noise.reseed(self.base.seed);
+//!     let height = self.noise.fbm(x / 100., y / 100., time);
+//!     let [dx, dy] = self.noise.curl(x / 100., y / 100., time);
+//!     // ...
+//! }
+//! ```
+use nannou::noise::{NoiseFn, OpenSimplex, Seedable};
+use std::f64::consts::TAU;
+
+/// Step used to differentiate the noise in [`LoopNoise::curl`].
+const EPSILON: f64 = 1e-4;
+/// Offsets decorrelating the two components of the domain warp.
+const WARP_OFFSET: [f64; 2] = [5.2, 1.3];
+
+/// Seeded 2D noise that loops over time, with fractal variants.
+#[derive(Clone)]
+pub struct LoopNoise {
+    noise: OpenSimplex,
+    seed: i32,
+    radius: f64,
+    /// The center of the time circle.
+    center: [f64; 2],
+    octaves: usize,
+    lacunarity: f64,
+    gain: f64,
+}
+
+impl LoopNoise {
+    pub fn new(seed: i32) -> LoopNoise {
+        LoopNoise {
+            noise: OpenSimplex::new().set_seed(seed as u32),
+            seed,
+            radius: 1.,
+            center: [0., 0.],
+            octaves: 4,
+            lacunarity: 2.,
+            gain: 0.5,
+        }
+    }
+
+    /// Change the seed, keeping the other settings. Nothing is recomputed if
+    /// the seed did not change, so this can be called at each frame.
+    pub fn reseed(&mut self, seed: i32) {
+        if seed != self.seed {
+            self.seed = seed;
+            self.noise = OpenSimplex::new().set_seed(seed as u32);
+        }
+    }
+
+    /// The radius of the circle followed in the noise over one loop. Larger
+    /// radii make the noise change faster.
+    pub fn radius(mut self, radius: f64) -> LoopNoise {
+        self.radius = radius;
+        self
+    }
+
+    /// Move the circle followed over one loop away from the origin, e.g. to
+    /// decorrelate two noises of the same seed.
+    pub fn center(mut self, x: f64, y: f64) -> LoopNoise {
+        self.center = [x, y];
+        self
+    }
+
+    /// The number of layers summed by the fractal functions.
+    pub fn octaves(mut self, octaves: usize) -> LoopNoise {
+        self.octaves = octaves.max(1);
+        self
+    }
+
+    /// The frequency multiplier from one octave to the next.
+    pub fn lacunarity(mut self, lacunarity: f64) -> LoopNoise {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    /// The amplitude multiplier from one octave to the next.
+    pub fn gain(mut self, gain: f64) -> LoopNoise {
+        self.gain = gain;
+        self
+    }
+
+    pub fn seed(&self) -> i32 {
+        self.seed
+    }
+
+    /// Noise between -1 and 1 at `(x, y)` and `time`, with a period of 1 in
+    /// time.
+    pub fn get(&self, x: f64, y: f64, time: f64) -> f64 {
+        self.sample(x, y, time, self.radius)
+    }
+
+    fn sample(&self, x: f64, y: f64, time: f64, radius: f64) -> f64 {
+        // `rem_euclid` makes time 1 exactly time 0, where the cosine and sine
+        // would differ by a rounding error.
+        let angle = TAU * time.rem_euclid(1.);
+        self.noise.get([
+            x,
+            y,
+            self.center[0] + radius * angle.cos(),
+            self.center[1] + radius * angle.sin(),
+        ])
+    }
+
+    /// Sum the octaves of `layer`, called with the frequency of each octave,
+    /// and divide by the sum of their amplitudes.
+    fn fractal(&self, layer: impl Fn(f64) -> f64) -> f64 {
+        let (mut sum, mut total) = (0., 0.);
+        let (mut frequency, mut amplitude) = (1., 1.);
+        for _ in 0..self.octaves {
+            sum += amplitude * layer(frequency);
+            total += amplitude;
+            frequency *= self.lacunarity;
+            amplitude *= self.gain;
+        }
+        sum / total
+    }
+
+    /// Fractal Brownian motion: octaves of noise of increasing frequency and
+    /// decreasing amplitude, between -1 and 1. The time circle grows with the
+    /// frequency, so that finer details also move faster.
+    pub fn fbm(&self, x: f64, y: f64, time: f64) -> f64 {
+        self.fractal(|f| self.sample(x * f, y * f, time, self.radius * f))
+    }
+
+    /// Ridged noise between 0 and 1, with sharp crests where the noise crosses
+    /// 0, like mountain ranges.
+    pub fn ridged(&self, x: f64, y: f64, time: f64) -> f64 {
+        self.fractal(|f| {
+            let ridge = 1. - self.sample(x * f, y * f, time, self.radius * f).abs();
+            ridge * ridge
+        })
+    }
+
+    /// Fractal noise at `(x, y)` displaced by another fractal noise, scaled by
+    /// `amount`. It gives swirling, marbled patterns.
+    pub fn warp(&self, x: f64, y: f64, time: f64, amount: f64) -> f64 {
+        let [dx, dy] = self.displacement(x, y, time);
+        self.fbm(x + amount * dx, y + amount * dy, time)
+    }
+
+    /// The displacement of the domain warp at `(x, y)`.
+    pub fn displacement(&self, x: f64, y: f64, time: f64) -> [f64; 2] {
+        [
+            self.fbm(x, y, time),
+            self.fbm(x + WARP_OFFSET[0], y + WARP_OFFSET[1], time),
+        ]
+    }
+
+    /// The curl of the noise at `(x, y)`: a divergence-free vector field, so
+    /// particles following it swirl without bunching up.
+    pub fn curl(&self, x: f64, y: f64, time: f64) -> [f64; 2] {
+        let dx = (self.fbm(x + EPSILON, y, time) - self.fbm(x - EPSILON, y, time)) / (2. * EPSILON);
+        let dy = (self.fbm(x, y + EPSILON, time) - self.fbm(x, y - EPSILON, time)) / (2. * EPSILON);
+        [dy, -dx]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LoopNoise;
+    use nannou::noise::{NoiseFn, OpenSimplex, Seedable};
+
+    #[test]
+    fn loops_exactly() {
+        let noise = LoopNoise::new(42).octaves(5);
+        for (x, y) in [(0.3, 0.7), (-12.5, 4.1), (100., -3.)] {
+            assert_eq!(noise.get(x, y, 0.), noise.get(x, y, 1.));
+            assert_eq!(noise.fbm(x, y, 0.), noise.fbm(x, y, 1.));
+            assert_eq!(noise.ridged(x, y, 0.), noise.ridged(x, y, 1.));
+            assert_eq!(noise.warp(x, y, 0., 2.), noise.warp(x, y, 1., 2.));
+            assert_eq!(noise.curl(x, y, 0.), noise.curl(x, y, 1.));
+            // And over several loops.
+            assert_eq!(noise.fbm(x, y, 0.25), noise.fbm(x, y, 2.25));
+        }
+        // The noise does move over the loop.
+        assert_ne!(noise.get(0.3, 0.7, 0.), noise.get(0.3, 0.7, 0.5));
+    }
+
+    #[test]
+    fn seeded() {
+        let mut noise = LoopNoise::new(1);
+        let value = noise.fbm(0.3, 0.7, 0.2);
+        assert_eq!(LoopNoise::new(1).fbm(0.3, 0.7, 0.2), value);
+        noise.reseed(2);
+        assert_ne!(noise.fbm(0.3, 0.7, 0.2), value);
+        assert_eq!(noise.seed(), 2);
+    }
+
+    #[test]
+    fn centered_time_circle() {
+        let noise = LoopNoise::new(5).radius(0.2).center(2., -1.);
+        let angle = std::f64::consts::TAU * 0.3;
+        let expected = OpenSimplex::new().set_seed(5).get([
+            0.4,
+            0.9,
+            2. + 0.2 * angle.cos(),
+            -1. + 0.2 * angle.sin(),
+        ]);
+        assert_eq!(noise.get(0.4, 0.9, 0.3), expected);
+        assert_eq!(noise.get(0.4, 0.9, 0.), noise.get(0.4, 0.9, 1.));
+    }
+
+    #[test]
+    fn ranges() {
+        let noise = LoopNoise::new(7);
+        for i in 0..200 {
+            let (x, y, time) = (i as f64 * 0.37, i as f64 * 0.11, i as f64 / 200.);
+            assert!(noise.fbm(x, y, time).abs() <= 1.);
+            assert!((0. ..=1.).contains(&noise.ridged(x, y, time)));
+        }
+    }
+
+    #[test]
+    fn curl_is_divergence_free() {
+        let noise = LoopNoise::new(3).octaves(2);
+        let (x, y, time, h) = (0.4, 0.9, 0.3, 1e-3);
+        let divergence = (noise.curl(x + h, y, time)[0] - noise.curl(x - h, y, time)[0]
+            + noise.curl(x, y + h, time)[1]
+            - noise.curl(x, y - h, time)[1])
+            / (2. * h);
+        assert!(divergence.abs() < 1e-2, "divergence {}", divergence);
+    }
+}
@@ -0,0 +1,251 @@
+//! Sequencing of an animation into named segments.
+//!
+//! A [`Timeline`] splits the time of an artwork, between 0 and 1, into
+//! segments that can each have their own easing, and that can overlap to
+//! crossfade from one to the next:
+//!
+//! ```ignore
+//! let timeline = Timeline::new()
+//!     .then("intro", 0.3)
+//!     .eased(Easing::CubicOut)
+//!     .crossfade(0.1)
+//!     .then("main", 0.7);
+//!
+//! if timeline.is_active("intro", time) {
+//!     let radius = 100. * timeline.progress("intro", time);
+//!     let alpha = timeline.weight("intro", time);
+//!     // ...
+//! }
+//! ```
+use super::easing::Easing;
+
+/// A named span of time of a [`Timeline`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment {
+    pub name: String,
+    pub start: f64,
+    pub end: f64,
+    /// The easing applied to the local time by [`Segment::progress`].
+    pub easing: Easing,
+    /// Durations over which the weight of the segment rises from 0 at its
+    /// start and falls back to 0 at its end.
+    pub fade_in: f64,
+    pub fade_out: f64,
+}
+
+impl Segment {
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+
+    /// The position of `time` in the segment, from 0 at its start to 1 at its
+    /// end, clamped outside of it.
+    pub fn local_time(&self, time: f64) -> f32 {
+        if self.duration() <= 0. {
+            return if time < self.start { 0. } else { 1. };
+        }
+        ((time - self.start) / self.duration()).clamp(0., 1.) as f32
+    }
+
+    /// The local time with the easing of the segment applied.
+    pub fn progress(&self, time: f64) -> f32 {
+        self.easing.apply(self.local_time(time))
+    }
+
+    /// The opacity of the segment at `time`: 1 in the middle of the segment,
+    /// ramping linearly over its fades, and 0 outside of it.
+    pub fn weight(&self, time: f64) -> f32 {
+        if time < self.start || time > self.end {
+            return 0.;
+        }
+        let ramp = |elapsed: f64, fade: f64| {
+            if fade > 0. {
+                (elapsed / fade).min(1.)
+            } else {
+                1.
+            }
+        };
+        ramp(time - self.start, self.fade_in).min(ramp(self.end - time, self.fade_out)) as f32
+    }
+}
+
+/// A segment active at a given time, as returned by [`Timeline::active`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ActiveSegment<'a> {
+    pub name: &'a str,
+    pub local_time: f32,
+    pub progress: f32,
+    pub weight: f32,
+}
+
+/// An ordered list of named segments.
+#[derive(Clone, Debug, Default)]
+pub struct Timeline {
+    segments: Vec<Segment>,
+    /// The overlap of the next segment added with [`Timeline::then`].
+    crossfade: f64,
+}
+
+impl Timeline {
+    pub fn new() -> Timeline {
+        Timeline::default()
+    }
+
+    /// Add a segment lasting `duration`, starting at the end of the previous
+    /// one, or earlier if [`Timeline::crossfade`] was called before.
+    pub fn then(self, name: &str, duration: f64) -> Timeline {
+        let start = self.end() - self.crossfade;
+        self.at(name, start, start + duration)
+    }
+
+    /// Add a segment between `start` and `end`, regardless of the others.
+    pub fn at(mut self, name: &str, start: f64, end: f64) -> Timeline {
+        let fade_in = self.crossfade;
+        if let Some(last) = self.segments.last_mut() {
+            last.fade_out = self.crossfade;
+        }
+        self.crossfade = 0.;
+        self.segments.push(Segment {
+            name: name.to_string(),
+            start,
+            end,
+            easing: Easing::Linear,
+            fade_in,
+            fade_out: 0.,
+        });
+        self
+    }
+
+    /// Set the easing of the last segment added.
+    pub fn eased(mut self, easing: Easing) -> Timeline {
+        if let Some(last) = self.segments.last_mut() {
+            last.easing = easing;
+        }
+        self
+    }
+
+    /// Make the next segment added start `overlap` before the end of the last
+    /// one, fading out the last segment while the next one fades in.
+    pub fn crossfade(mut self, overlap: f64) -> Timeline {
+        self.crossfade = overlap;
+        self
+    }
+
+    /// The end of the latest segment.
+    pub fn end(&self) -> f64 {
+        self.segments.iter().map(|s| s.end).fold(0., f64::max)
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Segment> {
+        self.segments.iter().find(|s| s.name == name)
+    }
+
+    /// The segment called `name`.
+    ///
+    /// Panics if there is no such segment, which is a typo in the artwork.
+    pub fn segment(&self, name: &str) -> &Segment {
+        self.find(name)
+            .unwrap_or_else(|| panic!("Unknown segment {}", name))
+    }
+
+    /// Whether `time` is in the segment called `name`. Segments include their
+    /// start but not their end, except at the end of the timeline.
+    pub fn is_active(&self, name: &str, time: f64) -> bool {
+        self.contains(self.segment(name), time)
+    }
+
+    fn contains(&self, segment: &Segment, time: f64) -> bool {
+        time >= segment.start
+            && (time < segment.end || time <= segment.end && segment.end >= self.end())
+    }
+
+    /// See [`Segment::local_time`].
+    pub fn local_time(&self, name: &str, time: f64) -> f32 {
+        self.segment(name).local_time(time)
+    }
+
+    /// See [`Segment::progress`].
+    pub fn progress(&self, name: &str, time: f64) -> f32 {
+        self.segment(name).progress(time)
+    }
+
+    /// See [`Segment::weight`].
+    pub fn weight(&self, name: &str, time: f64) -> f32 {
+        self.segment(name).weight(time)
+    }
+
+    /// The segments active at `time`, in the order they were added.
+    pub fn active(&self, time: f64) -> Vec<ActiveSegment<'_>> {
+        self.segments
+            .iter()
+            .filter(|s| self.contains(s, time))
+            .map(|s| ActiveSegment {
+                name: &s.name,
+                local_time: s.local_time(time),
+                progress: s.progress(time),
+                weight: s.weight(time),
+            })
+            .collect()
+    }
+
+    /// The name of the latest segment started at `time`.
+    pub fn current(&self, time: f64) -> Option<&str> {
+        self.segments
+            .iter()
+            .filter(|s| self.contains(s, time))
+            .max_by(|a, b| a.start.total_cmp(&b.start))
+            .map(|s| s.name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Timeline;
+    use crate::utils::easing::Easing;
+
+    #[test]
+    fn sequential_segments() {
+        let timeline = Timeline::new()
+            .then("a", 0.25)
+            .then("b", 0.5)
+            .eased(Easing::CubicIn)
+            .then("c", 0.25);
+        assert_eq!(timeline.end(), 1.);
+        assert_eq!(timeline.current(0.1), Some("a"));
+        assert_eq!(timeline.current(0.25), Some("b"));
+        assert_eq!(timeline.current(1.), Some("c"));
+        assert!(!timeline.is_active("a", 0.25));
+        assert_eq!(timeline.local_time("b", 0.5), 0.5);
+        assert_eq!(timeline.progress("b", 0.5), 0.125);
+        assert_eq!(timeline.local_time("b", 0.1), 0.);
+        assert_eq!(timeline.local_time("b", 0.9), 1.);
+        assert_eq!(timeline.weight("a", 0.1), 1.);
+        assert_eq!(timeline.weight("a", 0.5), 0.);
+    }
+
+    #[test]
+    fn crossfade() {
+        let timeline = Timeline::new().then("a", 0.6).crossfade(0.2).then("b", 0.6);
+        let b = timeline.segment("b");
+        assert!((b.start - 0.4).abs() < 1e-9);
+        assert!((timeline.end() - 1.).abs() < 1e-9);
+
+        let active = timeline.active(0.45);
+        assert_eq!(active.len(), 2);
+        assert!((active[0].weight - 0.75).abs() < 1e-6);
+        assert!((active[0].weight + active[1].weight - 1.).abs() < 1e-6);
+        assert_eq!(timeline.current(0.45), Some("b"));
+        assert_eq!(timeline.weight("a", 0.2), 1.);
+        assert_eq!(timeline.weight("b", 0.8), 1.);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown segment d")]
+    fn unknown_segment() {
+        Timeline::new().then("a", 1.).progress("d", 0.5);
+    }
+}
@@ -0,0 +1,19 @@
+//! The golden-image tests of the artworks with references, run by
+//! `golden_test.sh`. They render in a window with the GPU, so they only run
+//! when asked to:
+//!
+//! ``` sh
+//! cargo test --test golden_images -- --ignored
+//! ```
+use std::process::Command;
+
+#[test]
+#[ignore = "renders the artworks in a window with the GPU"]
+fn golden_images() {
+    let status = Command::new("sh")
+        .arg("golden_test.sh")
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .status()
+        .expect("could not run golden_test.sh");
+    assert!(status.success());
+}